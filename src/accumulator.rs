@@ -0,0 +1,47 @@
+//! The XOR at the heart of `xor` and `check`, one line at a time
+
+/// Running XOR of every line mixed in since the last [`reset`](Self::reset)
+#[derive(Debug, Clone, Copy)]
+pub struct XorAccumulator<const N: usize> {
+    buf: [u8; N],
+}
+
+impl<const N: usize> Default for XorAccumulator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> XorAccumulator<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { buf: [0; N] }
+    }
+    /// Start over from all zeroes
+    pub const fn reset(&mut self) {
+        self.buf = [0; N];
+    }
+    /// XOR `line` in
+    ///
+    /// # Panics
+    ///
+    /// If `line` isn't exactly `N` bytes
+    pub fn mix(&mut self, line: &[u8]) {
+        assert_eq!(line.len(), N, "can only mix in lines of {N} bytes");
+        for (s, b) in self.buf.iter_mut().zip(line) {
+            *s ^= b;
+        }
+    }
+    /// The XOR of everything mixed in so far
+    #[must_use]
+    pub const fn finish(&self) -> [u8; N] {
+        self.buf
+    }
+    /// Whether everything mixed in cancelled out, like a secret and all its shares do
+    ///
+    /// Every byte is looked at, so how long this takes doesn't tell how many matched
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        crate::ct::is_zero(&self.buf)
+    }
+}
@@ -0,0 +1,213 @@
+//! Looking for pad reuse across files that are supposed to be independent,
+//! and for sources that don't look random at all, for `split --audit-source`
+//!
+//! Only SHA-256 hashes of the lines are kept in memory, never the lines themselves.
+
+use bitcoin_hashes::{sha256, Hash};
+use clap::{ArgEnum, Args};
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    assert_one_stdin, check_width,
+    error::Failure,
+    format::{Encoding, Style},
+    meta, open_input, read_data_line, skip_lines,
+    sort::{self, SortOrder},
+    Range,
+};
+
+#[derive(Debug, Args)]
+pub struct Audit {
+    /// Files of a share set, or a directory holding them
+    #[clap(required = true, parse(from_os_str))]
+    source: Vec<PathBuf>,
+    /// Also spell out how many of the shares it takes to get the secret back
+    #[clap(long)]
+    count_shares_required: bool,
+    /// Order of the files in a directory, which their numbers in the report follow
+    #[clap(long, arg_enum, value_name = "ORDER", default_value = "natural")]
+    sort: SortOrder,
+    #[clap(flatten)]
+    style: Style,
+}
+
+/// Report every line that holds the same entropy as an earlier one, and fail if there are any
+pub fn run(
+    Audit {
+        source,
+        count_shares_required,
+        sort,
+        style,
+    }: Audit,
+) {
+    let source = expand_dirs(source, sort);
+    assert!(
+        source.len() >= 2,
+        "a share set has at least 2 files, {} given",
+        source.len()
+    );
+    assert_one_stdin(&source);
+    let style = meta::configure(&source, style, &mut Range::default());
+    let mut seen: HashMap<sha256::Hash, (usize, usize)> = HashMap::new();
+    let mut reused = 0;
+    let str = &mut String::new();
+    for (f, path) in source.iter().enumerate() {
+        let mut file = open_input(path);
+        let mut i = 0;
+        while read_data_line(&mut file, str, Some(f), i) != 0 {
+            let entropy = match style.decode(str, i + 1) {
+                Ok(entropy) => entropy,
+                Err(e) => Failure::new("parse", e).file(f).line(i).raise(),
+            };
+            str.clear();
+            check_width(&entropy, style.bytes(), Some(f), i);
+            match seen.entry(sha256::Hash::hash(&entropy)) {
+                Entry::Occupied(first) => {
+                    let &(first_f, first_i) = first.get();
+                    println!(
+                        "line {} of {} reuses line {} of {}",
+                        i + 1,
+                        path.display(),
+                        first_i + 1,
+                        source[first_f].display()
+                    );
+                    reused += 1;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((f, i));
+                }
+            }
+            i += 1;
+        }
+    }
+    if count_shares_required {
+        print_shares_required(source.len());
+    }
+    if reused != 0 {
+        eprintln!("{reused} lines repeat earlier ones, so their pads aren't independent");
+        crate::report::exit(1);
+    }
+    eprintln!(
+        "no line repeats within or across the {} files",
+        source.len()
+    );
+}
+
+/// The files in any directories among `paths`, in `order` and without `.meta`s, instead of them
+fn expand_dirs(paths: Vec<PathBuf>, order: SortOrder) -> Vec<PathBuf> {
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path);
+            continue;
+        }
+        let mut found: Vec<_> = std::fs::read_dir(&path)
+            .unwrap_or_else(|err| panic!("couldn't list {}: {err}", path.display()))
+            .map(|entry| entry.expect("couldn't list").path())
+            .filter(|p| p.is_file() && p.extension().is_none_or(|ext| ext != "meta"))
+            .collect();
+        sort::sort(&mut found, order);
+        files.extend(found);
+    }
+    files
+}
+
+/// What recovering an XOR set of `n` shares takes, unlike a threshold scheme
+fn print_shares_required(n: usize) {
+    println!("scheme: XOR, {n} of {n}");
+    println!(
+        "you need ALL {n} of these to recover the secret, any {} of them say nothing about it",
+        n - 1
+    );
+    println!("unlike a Shamir k of {n} set, there are no spares: losing one loses the secret");
+}
+
+/// What `split --audit-source` does about lines that look made up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum SourceAudit {
+    /// Only report them
+    Warn,
+    /// Refuse to split
+    Strict,
+}
+
+/// `split --audit-source`, stopping everything in `Strict` `mode` if any lines look weak
+pub fn audit_source(source: &Path, style: &Style, range: Range, mode: SourceAudit) {
+    assert!(
+        source.as_os_str() != "-",
+        "--audit-source reads the source before splitting it, so it can't be standard input"
+    );
+    let weak = scan_source(source, style, range);
+    if weak != 0 && mode == SourceAudit::Strict {
+        Failure::new(
+            "audit",
+            format_args!("{weak} source lines look weak, not splitting them"),
+        )
+        .raise();
+    }
+}
+
+/// Report the lines of `range` of `source` whose entropy has obvious structure, and count them
+///
+/// These are only heuristics: a pass means nothing, but random data almost never fails
+fn scan_source(source: &Path, style: &Style, range: Range) -> usize {
+    let mut file = open_input(source);
+    skip_lines(&mut file, range.read_past());
+    let str = &mut String::new();
+    let mut i = range.skip();
+    let mut weak = 0;
+    while !range.done(i) && read_data_line(&mut file, str, None, i) != 0 {
+        let entropy = match style.decode(str, i + 1) {
+            Ok(entropy) => entropy,
+            Err(e) => Failure::new("parse", e).line(i).raise(),
+        };
+        str.clear();
+        if let Some(why) = structure(&entropy) {
+            eprintln!("line {} of {} {why}", i + 1, source.display());
+            weak += 1;
+        }
+        i += 1;
+    }
+    if weak != 0 {
+        crate::log::warn(format_args!("{weak} lines don't look randomly generated"));
+    }
+    weak
+}
+
+/// What gives away `entropy` as not random, if anything
+fn structure(entropy: &[u8]) -> Option<String> {
+    if let [first, second, ..] = entropy {
+        let step = second.wrapping_sub(*first);
+        if entropy.windows(2).all(|w| w[1].wrapping_sub(w[0]) == step) {
+            return Some(match step {
+                0 => "is one byte repeated".to_owned(),
+                _ => format!("counts in steps of {step}"),
+            });
+        }
+    }
+    for (i, window) in entropy.windows(8).enumerate() {
+        if let Some(j) = entropy
+            .windows(8)
+            .skip(i + 1)
+            .position(|other| other == window)
+        {
+            return Some(format!("repeats 8 bytes, at byte {i} and {}", i + 1 + j));
+        }
+    }
+    let mut seen = [false; 256];
+    for &b in entropy {
+        seen[usize::from(b)] = true;
+    }
+    let distinct = seen.iter().filter(|&&seen| seen).count();
+    if distinct <= entropy.len() / 4 {
+        return Some(format!("only has {distinct} different bytes"));
+    }
+    if entropy.iter().all(|b| (0x20..0x7f).contains(b)) {
+        return Some("is all printable ASCII, like text".to_owned());
+    }
+    None
+}
@@ -0,0 +1,54 @@
+//! `--auto-buffer`, sizing the read buffer of each source file after the file and the memory free
+//!
+//! Small files keep the usual 8 KiB. Larger ones get a tenth of their size, up to 8 MiB
+//! and never more than a small part of the memory still available.
+
+use std::{
+    fs::File,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::log;
+
+static AUTO: AtomicBool = AtomicBool::new(false);
+
+/// The capacity `BufReader` has by default
+const DEFAULT: usize = 8 << 10;
+const MAX: u64 = 8 << 20;
+
+pub fn set(auto: bool) {
+    AUTO.store(auto, Ordering::Relaxed);
+}
+
+/// How much to buffer reading `file`, opened from `path`
+pub fn capacity(path: &Path, file: &File) -> usize {
+    if !AUTO.load(Ordering::Relaxed) {
+        return DEFAULT;
+    }
+    let size = file.metadata().map_or(0, |meta| meta.len());
+    let mut capacity = (size / 10).min(MAX);
+    if let Some(available) = available_memory() {
+        capacity = capacity.min(available / 64);
+    }
+    let capacity = usize::try_from(capacity).unwrap_or(usize::MAX).max(DEFAULT);
+    log::debug(format_args!(
+        "buffering {capacity} bytes of {} at a time",
+        path.display()
+    ));
+    capacity
+}
+
+/// `MemAvailable` from `/proc/meminfo`, where there is one
+fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let kib = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib << 10)
+}
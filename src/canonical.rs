@@ -0,0 +1,103 @@
+//! `canonicalize`, rewriting a hand-edited file exactly as xoriaz would have written it
+//!
+//! Each line is read as leniently as ever, past stray spaces, letter case and `\r\n`,
+//! and written back the one way its style writes it, so the result passes `--strict-whitespace`.
+
+use clap::Args;
+
+use std::{io::stdout, path::PathBuf};
+
+use crate::{
+    create_file, finish_file,
+    format::{Separator, Style},
+    log, meta, open_input, report,
+    transform::transform,
+    verify, Output, Range,
+};
+
+#[derive(Debug, Args)]
+pub struct Canonicalize {
+    /// File to clean up, a `mac:` line at its end is left out since it only matched the old bytes
+    #[clap(parse(from_os_str))]
+    source: PathBuf,
+    /// File to write the result to, stdout when left out or `-`
+    #[clap(short = 'o', long = "out", parse(from_os_str))]
+    dest: Option<PathBuf>,
+    /// Replace SOURCE itself, by writing next to it and renaming over it once read back
+    #[clap(long, conflicts_with = "dest")]
+    in_place: bool,
+    #[clap(flatten)]
+    style: Style,
+    #[clap(flatten)]
+    output: Output,
+}
+
+pub fn run(
+    Canonicalize {
+        source,
+        dest,
+        in_place,
+        style,
+        output,
+    }: Canonicalize,
+) {
+    let style = Style {
+        strict_whitespace: false,
+        ..meta::configure(std::slice::from_ref(&source), style, &mut Range::default())
+    };
+    report::style(&style);
+    assert!(
+        style.word_separator != Separator::Newline,
+        "--word-separator newline can't be read back, so it can't be canonicalized"
+    );
+    let dest = if in_place {
+        assert!(
+            source.as_os_str() != "-",
+            "--in-place needs a file, not standard input"
+        );
+        let name = source.file_name().expect("a file").to_string_lossy();
+        Some(source.with_file_name(format!(".{name}.xoriaz-canonical-{}", std::process::id())))
+    } else {
+        dest.filter(|path| path.as_os_str() != "-")
+    };
+    let mut file = open_input(&source);
+    let Some(path) = dest else {
+        transform(
+            &mut file,
+            stdout().lock(),
+            &style,
+            &style,
+            style.bytes(),
+            Range::default(),
+            |_| {},
+        );
+        return;
+    };
+    let mut w = create_file(&path, output);
+    let lines = transform(
+        &mut file,
+        &mut w,
+        &style,
+        &style,
+        style.bytes(),
+        Range::default(),
+        |_| {},
+    );
+    finish_file(w);
+    drop(file);
+    let strict = Style {
+        strict_whitespace: true,
+        ..style
+    };
+    verify::lines(&path, 0, &strict, lines);
+    if in_place {
+        std::fs::rename(&path, &source).unwrap_or_else(|err| {
+            panic!(
+                "couldn't replace {} with {}: {err}",
+                source.display(),
+                path.display()
+            )
+        });
+        log::info(format_args!("replaced {}", source.display()));
+    }
+}
@@ -0,0 +1,128 @@
+//! `--checksum-share`, an extra file with a hash of each line of the secret
+//!
+//! It tells whether `xor` put a line back together right, even without the original.
+//! It doesn't help getting the secret back, but it does confirm a correct guess of a whole line.
+//! The hash is the `--checksum-algorithm` one, the same as under `--mac`.
+
+use bitcoin_hashes::hex::{FromHex, ToHex};
+
+use std::{
+    io::{BufRead, Write},
+    path::Path,
+};
+
+use crate::{error::Failure, mac::Algorithm, open_input, read_data_line, skip_lines, Input};
+
+/// Where `split` writes the checksum lines
+pub struct Writer<'a> {
+    out: &'a mut dyn Write,
+    algorithm: Algorithm,
+}
+
+impl<'a> Writer<'a> {
+    pub fn new(out: &'a mut dyn Write, algorithm: Algorithm) -> Self {
+        Self { out, algorithm }
+    }
+    /// Write the checksum line of the secret `entropy`
+    pub fn write(&mut self, entropy: &[u8]) -> std::io::Result<()> {
+        writeln!(self.out, "{}", self.algorithm.digest(entropy).to_hex())
+    }
+}
+
+/// Compares the lines `xor` puts together against a checksum share
+pub struct Verifier {
+    file: Input,
+    algorithm: Algorithm,
+    line: String,
+    mismatched: usize,
+}
+
+impl Verifier {
+    /// Open the checksum share at `path`, from its line `skip` on
+    pub fn open(path: &Path, skip: usize, algorithm: Algorithm) -> Self {
+        let mut file = open_input(path);
+        skip_lines(&mut file, skip);
+        Self {
+            file,
+            algorithm,
+            line: String::new(),
+            mismatched: 0,
+        }
+    }
+    /// Report it if the 0-based line `i` came out as something other than what was split
+    pub fn check(&mut self, i: usize, entropy: &[u8]) {
+        self.line.clear();
+        if read_data_line(&mut self.file, &mut self.line, None, i) == 0 {
+            Failure::new("checksum", "the checksum share ended early")
+                .line(i)
+                .raise();
+        }
+        let expected = Vec::<u8>::from_hex(self.line.trim())
+            .unwrap_or_else(|e| Failure::new("checksum", e).line(i).raise());
+        let actual = self.algorithm.digest(entropy);
+        if expected.len() != actual.len() {
+            Failure::new(
+                "checksum",
+                format_args!(
+                    "the checksum share isn't {}, try --checksum-algorithm",
+                    self.algorithm.name()
+                ),
+            )
+            .line(i)
+            .raise();
+        }
+        if !crate::ct::eq(&expected, &actual) {
+            eprintln!("line {} doesn't match the checksum share", i + 1);
+            self.mismatched += 1;
+        }
+    }
+    /// Fail if any line didn't match, or the checksum share goes on after the last one
+    pub fn finish(mut self) {
+        self.line.clear();
+        if self
+            .file
+            .read_line(&mut self.line)
+            .is_ok_and(|len| len != 0)
+        {
+            Failure::new(
+                "checksum",
+                "the checksum share has more lines than the result",
+            )
+            .raise();
+        }
+        if self.mismatched != 0 {
+            Failure::new(
+                "checksum",
+                format_args!("{} lines don't match the checksum share", self.mismatched),
+            )
+            .raise();
+        }
+        eprintln!("every line matches the checksum share");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{failure, scratch};
+
+    #[test]
+    fn uses_the_checksum_algorithm() {
+        let path = scratch("checksum-sha512");
+        let mut out = Vec::new();
+        let mut writer = Writer::new(&mut out, Algorithm::Sha512);
+        writer.write(b"zero").unwrap();
+        writer.write(b"one").unwrap();
+        std::fs::write(&path, &out).unwrap();
+        assert_eq!(out.len(), 2 * (128 + 1));
+
+        let mut verifier = Verifier::open(&path, 1, Algorithm::Sha512);
+        verifier.check(1, b"one");
+        verifier.finish();
+
+        let mut verifier = Verifier::open(&path, 0, Algorithm::Sha256);
+        let why = failure(|| verifier.check(0, b"zero"));
+        assert_eq!((why.kind, why.line), ("checksum", Some(0)));
+        std::fs::remove_file(path).unwrap();
+    }
+}
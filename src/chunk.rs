@@ -0,0 +1,64 @@
+//! `--chunk-lines`, collecting several whole lines before handing them to the output at once
+//!
+//! The bytes come out the same, only in fewer and bigger writes.
+//! That matters most for stdout, which otherwise makes a write for every line.
+
+use std::io::Write;
+
+/// Holds what's written to it until `every` lines are complete, then writes them to `inner`
+pub struct Chunked<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+    lines: usize,
+    every: usize,
+}
+
+impl<W: Write> Chunked<W> {
+    /// Batches of `every` lines, where 0 or 1 writes everything through right away
+    pub const fn new(inner: W, every: usize) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+            lines: 0,
+            every,
+        }
+    }
+    /// Write the lines collected so far, without flushing `inner`
+    fn drain(&mut self) -> std::io::Result<()> {
+        self.inner.write_all(&self.buf)?;
+        self.buf.fill(0);
+        self.buf.clear();
+        self.lines = 0;
+        Ok(())
+    }
+    /// Write out the last, partial batch
+    pub fn finish(mut self) {
+        self.drain()
+            .unwrap_or_else(|err| panic!("couldn't write: {err}"));
+    }
+}
+
+impl<W: Write> Write for Chunked<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.every <= 1 {
+            return self.inner.write(buf);
+        }
+        self.buf.extend_from_slice(buf);
+        self.lines += crate::count::count_newlines(buf);
+        if self.lines >= self.every {
+            self.drain()?;
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.drain()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for Chunked<W> {
+    fn drop(&mut self) {
+        // lines that didn't make it out are secret all the same
+        self.buf.fill(0);
+    }
+}
@@ -0,0 +1,58 @@
+//! Copying a recovered secret to the system clipboard, through whichever tool the platform has
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Commands that take the new clipboard content on their standard input
+const TOOLS: &[&[&str]] = if cfg!(windows) {
+    &[&["clip"]]
+} else if cfg!(target_os = "macos") {
+    &[&["pbcopy"]]
+} else {
+    &[
+        &["wl-copy"],
+        &["xclip", "-selection", "clipboard"],
+        &["xsel", "--clipboard", "--input"],
+    ]
+};
+
+/// Put `text` on the clipboard, passing it through a pipe so it never shows up in process listings
+pub fn copy(text: &[u8]) {
+    for tool in TOOLS {
+        let child = Command::new(tool[0])
+            .args(&tool[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else { continue };
+        let written = child.stdin.take().expect("piped").write_all(text);
+        if written.is_ok() && child.wait().is_ok_and(|status| status.success()) {
+            return;
+        }
+    }
+    let names: Vec<_> = TOOLS.iter().map(|tool| tool[0]).collect();
+    panic!(
+        "couldn't copy to the clipboard, none of {} worked",
+        names.join(", ")
+    );
+}
+
+/// Put the single line `secret` on the clipboard, and clear it again after `clear_after` seconds
+pub fn copy_line(mut secret: Vec<u8>, clear_after: Option<u64>) {
+    let lines = secret.split_inclusive(|&b| b == b'\n').count();
+    if lines != 1 {
+        secret.fill(0);
+        panic!("only a single line can go to the clipboard, but the result has {lines}, pick one with --from-line and --to-line");
+    }
+    copy(secret.trim_ascii_end());
+    secret.fill(0);
+    eprintln!("copied to the clipboard");
+    if let Some(seconds) = clear_after {
+        std::thread::sleep(std::time::Duration::from_secs(seconds));
+        copy(b"");
+        eprintln!("cleared the clipboard");
+    }
+}
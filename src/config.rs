@@ -0,0 +1,120 @@
+//! Defaults for command line options, read from a small subset of TOML
+//!
+//! Every `key = value` line sets the default of the `--key` option of any
+//! subcommand that has one, or of the global one, so flags given explicitly still win.
+//! Values are integers, booleans or double-quoted strings without escapes.
+//! A flag set to `true` this way can't be switched off again on the command line.
+
+use clap::Command;
+
+use std::path::Path;
+
+use crate::error::Failure;
+
+/// Find the value of `--config`, before clap gets to parse anything
+pub fn path_from_args() -> Option<std::path::PathBuf> {
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--config" {
+            return args.next().map(Into::into);
+        }
+        if let Some(path) = arg.to_str().and_then(|a| a.strip_prefix("--config=")) {
+            return Some(path.into());
+        }
+    }
+    None
+}
+
+/// Whether the flag `--name` is given, before clap gets to parse anything
+pub fn flag_from_args(name: &str) -> bool {
+    std::env::args_os()
+        .skip(1)
+        .take_while(|arg| arg != "--")
+        .any(|arg| arg == name)
+}
+
+/// Replace option defaults in `cmd` and its subcommands with the ones in the file at `path`
+pub fn apply(mut cmd: Command<'static>, path: &Path) -> Command<'static> {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|err| {
+        Failure::new(
+            "config",
+            format_args!("couldn't read config {}: {err}", path.display()),
+        )
+        .raise()
+    });
+    for (n, line) in text.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fail = |why: &dyn std::fmt::Display| -> ! {
+            Failure::new("config", format_args!("{}: {why}", path.display()))
+                .line(n)
+                .raise()
+        };
+        let (key, value) = parse_line(line).unwrap_or_else(|why| fail(&why));
+        let key: &'static str = Box::leak(key.replace('_', "-").into_boxed_str());
+        let value: &'static str = Box::leak(value.into_boxed_str());
+        let mut found = set_default(&mut cmd, key, value);
+        for sub in cmd.get_subcommands_mut() {
+            found |= set_default(sub, key, value);
+        }
+        if !found {
+            fail(&format_args!("no command has a `{key}` option"));
+        }
+    }
+    cmd
+}
+
+/// Make `value` the default of the `--key` option of `cmd` itself, if it has one
+fn set_default(cmd: &mut Command<'static>, key: &'static str, value: &'static str) -> bool {
+    let arg = cmd
+        .get_arguments()
+        .find(|a| a.get_id() == key && a.get_long().is_some() && key != "config");
+    let Some(arg) = arg else { return false };
+    // flags can only be switched on, by pretending they're always given
+    if arg.is_takes_value_set() || value != "false" {
+        *cmd = std::mem::take(cmd).mut_arg(key, |a| a.default_value(value));
+    }
+    true
+}
+
+pub fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Split a `key = value` line, with the quotes taken off string values
+pub fn parse_line(line: &str) -> Result<(&str, String), &'static str> {
+    let (key, value) = line.split_once('=').ok_or("expected `key = value`")?;
+    let (key, value) = (key.trim(), value.trim());
+    if key.is_empty()
+        || !key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+    {
+        return Err("keys must be bare words");
+    }
+    let value = if let Some(s) = value.strip_prefix('"') {
+        let s = s.strip_suffix('"').ok_or("unterminated string")?;
+        if s.contains(['"', '\\']) {
+            return Err("escapes in strings aren't supported");
+        }
+        s.to_owned()
+    } else if value == "true" || value == "false" || value.parse::<i64>().is_ok() {
+        value.to_owned()
+    } else {
+        return Err("values must be integers, booleans or strings");
+    };
+    Ok((key, value))
+}
@@ -0,0 +1,52 @@
+//! `count`, line counts of files without decoding any of their lines
+//!
+//! Lines are counted like `wc -l` does, by their newlines, plus one for a last line without.
+//! Blank lines at the end and a `mac:` line are counted too, `info` leaves those out.
+
+use clap::Args;
+
+use std::{io::BufRead, path::PathBuf};
+
+use crate::{assert_one_stdin, open_input};
+
+#[derive(Debug, Args)]
+pub struct Count {
+    /// Files to count the lines of, `-` for standard input
+    #[clap(required = true, parse(from_os_str))]
+    source: Vec<PathBuf>,
+}
+
+pub fn run(Count { source }: Count) {
+    assert_one_stdin(&source);
+    let mut total = 0;
+    for path in &source {
+        let lines = count_lines(&mut open_input(path))
+            .unwrap_or_else(|err| panic!("couldn't read {}: {err}", path.display()));
+        println!("{lines}\t{}", path.display());
+        total += lines;
+    }
+    if source.len() > 1 {
+        println!("{total}\ttotal");
+    }
+}
+
+/// The lines `file` has left, counted by their newlines
+pub fn count_lines(file: &mut impl BufRead) -> std::io::Result<usize> {
+    let (mut lines, mut last) = (0, b'\n');
+    loop {
+        let buf = file.fill_buf()?;
+        let Some(&end) = buf.last() else { break };
+        lines += count_newlines(buf);
+        last = end;
+        let len = buf.len();
+        file.consume(len);
+    }
+    Ok(lines + usize::from(last != b'\n'))
+}
+
+/// How many `\n` there are in `bytes`
+pub fn count_newlines(bytes: &[u8]) -> usize {
+    // no memchr or bytecount crate around, and this loop vectorizes well enough
+    #[allow(clippy::naive_bytecount)]
+    bytes.iter().filter(|&&b| b == b'\n').count()
+}
@@ -0,0 +1,32 @@
+//! Comparisons of secrets that look at every byte, so how long they take doesn't tell
+//! how many bytes matched
+
+use std::hint::black_box;
+
+/// Whether every byte of `bytes` is zero
+#[must_use]
+pub fn is_zero(bytes: &[u8]) -> bool {
+    let diff = bytes.iter().fold(0, |diff, &b| diff | black_box(b));
+    black_box(diff) == 0
+}
+
+/// Whether `a` and `b` are the same, their lengths aren't secret
+#[must_use]
+pub fn eq(a: &[u8], b: &[u8]) -> bool {
+    let diff = std::iter::zip(a, b).fold(0, |diff, (a, b)| diff | black_box(a ^ b));
+    a.len() == b.len() && black_box(diff) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares() {
+        assert!(is_zero(&[0; 32]));
+        assert!(!is_zero(&[0, 0, 1]));
+        assert!(eq(b"secret", b"secret"));
+        assert!(!eq(b"secret", b"secreT"));
+        assert!(!eq(b"secret", b"secret!"));
+    }
+}
@@ -0,0 +1,65 @@
+//! Calendar dates in UTC, as days since 1970-01-01, for `--not-before` and timestamped names
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date(u64);
+
+impl Date {
+    pub fn today() -> Self {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("clock is before 1970")
+            .as_secs();
+        Self(secs / 86400)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = civil(self.0);
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// A `YYYY-MM-DD` date, from 1970 on
+pub fn parse(s: &str) -> Result<Date, String> {
+    let fields: Vec<_> = s.trim().splitn(3, '-').collect();
+    let [year, month, day] = fields[..] else {
+        return Err("dates are written like 2030-12-31".to_owned());
+    };
+    let number = |field: &str| field.parse::<u64>().map_err(|e| format!("{e}"));
+    let (year, month, day) = (number(year)?, number(month)?, number(day)?);
+    if year < 1970 || !(1..=12).contains(&month) || day == 0 {
+        return Err(format!("{s} isn't a date from 1970 on"));
+    }
+    let date = Date(days(year, month, day));
+    if civil(date.0) != (year, month, day) {
+        return Err(format!("{s} isn't a day of the calendar"));
+    }
+    Ok(date)
+}
+
+/// The year, month and day of `days` since 1970-01-01
+// from http://howardhinnant.github.io/date_algorithms.html
+pub const fn civil(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let (era, doe) = (z / 146_097, z % 146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = era * 400 + yoe + (month <= 2) as u64;
+    (year, month, day)
+}
+
+/// The days since 1970-01-01 of a date from then on, the inverse of [`civil`]
+const fn days(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let (era, yoe) = (year / 400, year % 400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
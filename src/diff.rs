@@ -0,0 +1,121 @@
+//! Comparing the entropy of two files line by line, whatever they're written in
+//!
+//! Only the positions of differing lines are printed, never the lines themselves.
+
+use clap::Args;
+
+use std::path::PathBuf;
+
+use crate::{
+    assert_one_stdin,
+    error::Failure,
+    format::{Encoding, Entropy, Format, Style},
+    meta, open_input, read_data_line, Input, Range,
+};
+
+#[derive(Debug, Args)]
+pub struct Diff {
+    #[clap(parse(from_os_str))]
+    left: PathBuf,
+    #[clap(parse(from_os_str))]
+    right: PathBuf,
+    /// Encoding of the right file, when it differs from `--format`, like words against hex
+    #[clap(long, arg_enum, value_name = "FORMAT")]
+    right_format: Option<Format>,
+    #[clap(flatten)]
+    style: Style,
+}
+
+/// Print where the entropy of the two files differs, and fail if it does anywhere
+pub fn run(
+    Diff {
+        left,
+        right,
+        right_format,
+        style,
+    }: Diff,
+) {
+    assert_one_stdin(&[left.clone(), right.clone()]);
+    let right_style = Style {
+        format: right_format.unwrap_or(style.format),
+        ..style.clone()
+    };
+    let left_style = meta::configure(std::slice::from_ref(&left), style, &mut Range::default());
+    let right_style = meta::configure(
+        std::slice::from_ref(&right),
+        right_style,
+        &mut Range::default(),
+    );
+    let mut sides = [
+        Side::new(open_input(&left), left_style, 0),
+        Side::new(open_input(&right), right_style, 1),
+    ];
+    let (mut i, mut differing) = (0, 0);
+    loop {
+        let [l, r] = &mut sides;
+        match (l.next(i), r.next(i)) {
+            (Some(l), Some(r)) => {
+                if !crate::ct::eq(&l, &r) {
+                    println!("differs at line {}", i + 1);
+                    differing += 1;
+                }
+            }
+            (None, None) => break,
+            (longer, _) => {
+                let (rest, longer, shorter) = if longer.is_some() {
+                    (l, &left, &right)
+                } else {
+                    (r, &right, &left)
+                };
+                let mut extra = 1;
+                while rest.next(i + extra).is_some() {
+                    extra += 1;
+                }
+                println!(
+                    "{} has {extra} more lines than {}, from line {}",
+                    longer.display(),
+                    shorter.display(),
+                    i + 1
+                );
+                differing += extra;
+                break;
+            }
+        }
+        i += 1;
+    }
+    if differing != 0 {
+        eprintln!("{differing} lines differ");
+        crate::report::exit(1);
+    }
+    eprintln!("all {i} lines hold the same entropy");
+}
+
+/// One of the files, read a line at a time
+struct Side {
+    file: Input,
+    style: Style,
+    f: usize,
+    line: String,
+}
+
+impl Side {
+    const fn new(file: Input, style: Style, f: usize) -> Self {
+        Self {
+            file,
+            style,
+            f,
+            line: String::new(),
+        }
+    }
+    /// The entropy of the 0-based line `i`, or nothing past the end
+    fn next(&mut self, i: usize) -> Option<Entropy> {
+        self.line.clear();
+        if read_data_line(&mut self.file, &mut self.line, Some(self.f), i) == 0 {
+            return None;
+        }
+        match self.style.decode(&self.line, i + 1) {
+            Ok(entropy) => Some(entropy),
+            Err(e) => Failure::new("parse", e).file(self.f).line(i).raise(),
+        }
+    }
+}
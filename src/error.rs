@@ -0,0 +1,126 @@
+//! Errors about the content of input files, which carry where they happened
+//!
+//! They unwind like any other panic, so the panic hook can print them either
+//! for humans or, with `--json-errors`, as a single JSON object.
+//! Lines and files are counted from 0 in here, but from 1 wherever they're shown.
+
+use std::{
+    fmt::{self, Display, Write},
+    panic::PanicHookInfo,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Once,
+    },
+};
+
+#[derive(Debug)]
+pub struct Failure {
+    /// Short machine readable category, like `parse`
+    pub kind: &'static str,
+    /// 0-based index of the input file, for commands that read several
+    pub file: Option<usize>,
+    /// 0-based index of the line
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn new(kind: &'static str, message: impl Display) -> Self {
+        Self {
+            kind,
+            file: None,
+            line: None,
+            message: message.to_string(),
+        }
+    }
+    pub const fn file(mut self, file: usize) -> Self {
+        self.file = Some(file);
+        self
+    }
+    pub const fn line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+    pub fn raise(self) -> ! {
+        std::panic::panic_any(self)
+    }
+    fn to_json(&self) -> String {
+        let mut out = format!("{{\"error\":{}", json_str(self.kind));
+        if let Some(file) = self.file {
+            write!(out, ",\"file\":{}", file + 1).unwrap();
+        }
+        if let Some(line) = self.line {
+            write!(out, ",\"line\":{}", line + 1).unwrap();
+        }
+        write!(out, ",\"message\":{}}}", json_str(&self.message)).unwrap();
+        out
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.line.map(|l| l + 1), self.file.map(|f| f + 1)) {
+            (Some(line), Some(file)) => write!(f, "error on line {line} in file {file}: ")?,
+            (Some(line), None) => write!(f, "error on line {line}: ")?,
+            (None, Some(file)) => write!(f, "error in file {file}: ")?,
+            (None, None) => {}
+        }
+        f.write_str(&self.message)
+    }
+}
+
+/// `s` quoted as a JSON string, which is also a valid TOML one
+pub fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c < ' ' => write!(out, "\\u{:04x}", u32::from(c)).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+static JSON: AtomicBool = AtomicBool::new(false);
+static HOOK: Once = Once::new();
+
+/// Print [`Failure`]s without the panic boilerplate, and everything as JSON if `json` is set
+///
+/// Either way the error ends up in the `--report-file` too.
+/// Calling it again only changes `json`, the hook stays installed once
+pub fn install_hook(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+    HOOK.call_once(install);
+}
+
+fn install() {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info: &PanicHookInfo| {
+        let payload = info.payload();
+        let failure = payload.downcast_ref::<Failure>();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown error");
+        crate::report::finish(Some(
+            &failure.map_or_else(|| message.to_owned(), ToString::to_string),
+        ));
+        if JSON.load(Ordering::Relaxed) {
+            let json = failure.map_or_else(
+                || Failure::new("other", message).to_json(),
+                Failure::to_json,
+            );
+            eprintln!("{json}");
+        } else if let Some(failure) = failure {
+            eprintln!("{failure}");
+        } else {
+            default(info);
+        }
+    }));
+}
@@ -0,0 +1,322 @@
+//! `--explain`, saying what a command line would do instead of doing it
+//!
+//! Nothing is created or decoded. Sources are only looked at for their `.meta`
+//! and their line count, and only their paths show up in the plan.
+//! `gen`, `split`, `xor` and `check` get a plan of their own, the other commands
+//! what they're for and the help of every option given to them.
+
+use std::path::{Path, PathBuf};
+
+use clap::{ArgMatches, CommandFactory, ValueSource};
+
+use crate::{
+    count::count_lines,
+    format::{Format, Style},
+    meta, open_input, share_names, timestamped, Check, Cli, Command, Gen, Range, Split, Xor,
+};
+
+pub fn run(command: &Command, matches: &ArgMatches) {
+    match command {
+        Command::Gen(args) => gen(args),
+        Command::Split(args) => split(args),
+        Command::Xor(args) => xor(args),
+        Command::Check(args) => check(args),
+        _ => {
+            if let Some((name, matches)) = matches.subcommand() {
+                generic(name, matches);
+            }
+        }
+    }
+}
+
+/// What the other commands are for, and each option given to them
+fn generic(name: &str, matches: &ArgMatches) {
+    let cli = Cli::command();
+    let Some(command) = cli.find_subcommand(name) else {
+        return;
+    };
+    println!("{name}: {}", command.get_about().unwrap_or_default());
+    for arg in command.get_arguments() {
+        let id = arg.get_id();
+        if arg.is_global_set() || matches.value_source(id) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+        let label = match (arg.get_long(), arg.get_short()) {
+            (Some(long), _) => format!("--{long}"),
+            (None, Some(short)) => format!("-{short}"),
+            (None, None) => arg
+                .get_value_names()
+                .and_then(|names| names.first())
+                .map_or_else(|| id.to_uppercase(), |name| (*name).to_owned()),
+        };
+        let values: Vec<_> = if arg.is_allow_invalid_utf8_set() {
+            let values = matches.values_of_os(id).into_iter().flatten();
+            values
+                .map(|v| format!(" {}", v.to_string_lossy()))
+                .collect()
+        } else {
+            let values = matches.values_of(id).into_iter().flatten();
+            values.map(|v| format!(" {v}")).collect()
+        };
+        match arg.get_help() {
+            Some(help) => println!("with {label}{}: {help}", values.concat()),
+            None => println!("with {label}{}", values.concat()),
+        }
+    }
+}
+
+fn gen(args: &Gen) {
+    let dest = args.timestamped_names.as_ref().map_or_else(
+        || args.dest.clone(),
+        |prefix| timestamped(prefix, args.count),
+    );
+    if args.of.is_some() {
+        return split_into("the line given with --of", &dest, &args.style);
+    }
+    if let Some(path) = &args.of_file {
+        return split_into(&source(path, &args.style), &dest, &args.style);
+    }
+    let lines = args.bytes.map_or_else(
+        || match args.lines {
+            0 => "lines until Ctrl-C".to_owned(),
+            lines => format!("{lines} lines"),
+        },
+        |bytes| format!("enough lines for {bytes} bytes"),
+    );
+    if args.count_only {
+        return println!("will print how much {lines} would take, and create nothing");
+    }
+    let what = if args.repeat.is_some() {
+        "the --repeat line over and over, which is NOT random,".to_owned()
+    } else {
+        format!("random {}", style(&args.style))
+    };
+    println!("will write {lines} of {what} to {}", paths(&dest));
+    if let Some(n) = args.rotate_lines {
+        println!("will roll each over into .001, .002, ... every {n} lines");
+    } else if let Some(size) = args.rotate_size {
+        println!("will roll each over into .001, .002, ... every {size} bytes or so");
+    }
+    if args.meta {
+        println!("will describe each in a .meta next to it");
+    }
+    if args.verify_written {
+        println!("will read each back once it's written");
+    }
+}
+
+fn split(args: &Split) {
+    let dest = args.shares.map_or_else(
+        || args.dest.clone(),
+        |total| share_names(total, &args.name_template, &args.output_dir),
+    );
+    println!(
+        "will read the secret from {}{}",
+        source(&args.source, &args.style),
+        range(args.range)
+    );
+    if args.audit_source.is_some() {
+        println!("will look for lines with obvious structure first");
+    }
+    let pads = args.pad_source.as_ref().map_or_else(
+        || "random pads from the system".to_owned(),
+        |path| format!("pads read from {}", path.display()),
+    );
+    match (args.framed, &args.interleave) {
+        (true, _) => println!("will write {} shares as frames to stdout", dest.len()),
+        (_, Some(path)) => println!(
+            "will write {} shares interleaved line by line to {}",
+            dest.len(),
+            path.display()
+        ),
+        _ => println!("will create {} shares: {}", dest.len(), paths(&dest)),
+    }
+    println!("will fill every share but the first with {pads}");
+    println!("will write the secret XORed with all those pads to the first");
+    if let Some(bytes) = &args.byte_range {
+        println!(
+            "will only mask bytes {}:{} of each line, the rest goes into the first share as it is",
+            bytes.start, bytes.end
+        );
+    }
+    if args.mac.is_some() {
+        println!("will end each share with a mac: line");
+    }
+    if let Some(path) = &args.checksum_share {
+        println!(
+            "will write a {} of each line to {}",
+            args.checksum_algorithm.name(),
+            path.display()
+        );
+    }
+    if args.meta {
+        println!("will describe each share in a .meta next to it");
+    }
+    for date in &args.not_before {
+        println!("will record that shares aren't to be used before {date}");
+    }
+    if args.verify_written {
+        println!("will read the shares back and check they XOR to the secret");
+    }
+    if !args.parity.is_empty() {
+        println!("will write parity files to {}", paths(&args.parity));
+    }
+    if let Some(sheet) = &args.sheet {
+        println!(
+            "will print each share to a PDF named after {}",
+            sheet.display()
+        );
+    }
+}
+
+/// The plan of `gen --of` and `--of-file`, which split like `split` does
+fn split_into(secret: &str, dest: &[PathBuf], style: &Style) {
+    println!("will read the secret from {secret}");
+    println!("will create {} shares: {}", dest.len(), paths(dest));
+    println!(
+        "will fill every share but the first with random pads, written as {}",
+        self::style(style)
+    );
+    println!("will write the secret XORed with all those pads to the first");
+}
+
+fn xor(args: &Xor) {
+    if let Some(list) = &args.files_from {
+        println!("will read more source paths from {}", list.display());
+    }
+    if let Some(archive) = &args.from_tar {
+        println!(
+            "will take the sources from the files in {}, with any .meta that comes along",
+            archive.display()
+        );
+    }
+    if let Some(n) = args.deinterleave {
+        println!("will take {n} shares apart from the one source");
+    }
+    if args.mac.is_some() {
+        println!("will check the mac: line of every source before anything else");
+    }
+    for path in &args.source {
+        println!("will read {}", source(path, &args.style));
+    }
+    if args.preview {
+        return println!(
+            "will print line counts and digests of the first and last lines, and stop"
+        );
+    }
+    println!(
+        "will XOR them together line by line{}{}",
+        range(args.range),
+        if args.fold {
+            ", a source at a time through temporary files"
+        } else {
+            ""
+        }
+    );
+    if args.promote {
+        println!("will zero-extend narrower lines to the widest one");
+    }
+    if let Some(schedule) = &args.schedule {
+        println!("will give line i only the sources of group i of {schedule}, round and round");
+        if let Some(source) = args.recover {
+            println!("will warn about the groups that leave out source {source}");
+        }
+    }
+    if let Some(bytes) = &args.byte_range {
+        println!(
+            "will only XOR bytes {}:{} and copy the rest from the first source",
+            bytes.start, bytes.end
+        );
+    }
+    if args.with_mnemonic.is_some() {
+        println!("will XOR the --with-mnemonic words into every line too");
+    }
+    if let Some(path) = &args.checksum_share {
+        println!(
+            "will check every line of the result against {}",
+            path.display()
+        );
+    }
+    let out = match &args.dest {
+        _ if args.clipboard => "the clipboard".to_owned(),
+        Some(path) if path.as_os_str() != "-" => path.display().to_string(),
+        _ => "stdout".to_owned(),
+    };
+    println!("will write the result to {out}");
+}
+
+fn check(args: &Check) {
+    for path in &args.source {
+        println!("will read {}", source(path, &args.style));
+    }
+    println!(
+        "will XOR them together and pass if {} comes out all zeros",
+        args.every_nth.map_or_else(
+            || "every line".to_owned(),
+            |k| format!("1 in {k} lines, from the first,")
+        )
+    );
+}
+
+/// A source, with its line count and style where they're known without decoding it
+fn source(path: &Path, style: &Style) -> String {
+    if path.as_os_str() == "-" {
+        return "standard input".to_owned();
+    }
+    if !path.exists() {
+        return format!("{}, which doesn't exist", path.display());
+    }
+    let (style, lines) = match meta::read(path) {
+        Some(meta) => (meta.style, meta.lines),
+        None => (
+            style.clone(),
+            count_lines(&mut open_input(path)).unwrap_or_default(),
+        ),
+    };
+    format!(
+        "{} ({lines} lines of {})",
+        path.display(),
+        self::style(&style)
+    )
+}
+
+fn style(style: &Style) -> String {
+    match style.format {
+        Format::Words => format!(
+            "{}-word {} mnemonics",
+            style.bits / 32 * 3,
+            crate::format::language_name(style.language)
+        ),
+        Format::Hex => format!("{}-bit hex", style.bits),
+        Format::Base58 => format!("{}-bit base58", style.bits),
+        Format::Bech32 => format!("{}-bit bech32 starting with {}", style.bits, style.hrp),
+    }
+}
+
+/// What of each source `range` takes, as the end of a sentence
+fn range(range: Range) -> String {
+    let mut parts = Vec::new();
+    if range.skip_header_lines != 0 {
+        parts.push(format!(", past {} header lines", range.skip_header_lines));
+    }
+    if !range.is_everything() {
+        let from = range.from_line.max(1);
+        parts.push(range.to_line.map_or_else(
+            || format!(", from line {from}"),
+            |to| format!(", from line {from} to {to}"),
+        ));
+    }
+    if let Some(n) = range.expect_lines {
+        parts.push(format!(", failing unless it's {n} lines"));
+    }
+    parts.concat()
+}
+
+/// `paths` as a list, or stdout when there are none
+fn paths(paths: &[PathBuf]) -> String {
+    if paths.is_empty() {
+        return "stdout".to_owned();
+    }
+    let names: Vec<_> = paths.iter().map(|p| p.display().to_string()).collect();
+    names.join(", ")
+}
@@ -0,0 +1,746 @@
+// the byte and digit arithmetic below truncates on purpose
+#![allow(clippy::cast_possible_truncation)]
+use bip39::{Language, Mnemonic};
+use clap::{ArgEnum, Args};
+use smallvec::SmallVec;
+use unicode_normalization::{is_nfkd_quick, IsNormalized};
+
+use std::{fmt, io::Write};
+
+/// Entropy decoded from a single line
+///
+/// Its length is exactly what the line held: unlike with `Mnemonic::to_entropy_array`,
+/// there are no padding bytes past the end that could get mixed in by mistake.
+pub type Entropy = SmallVec<[u8; 32]>;
+
+/// How a line's entropy is written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum Format {
+    /// BIP39 mnemonic words
+    Words,
+    /// Lowercase hexadecimal
+    Hex,
+    /// Bitcoin-alphabet base58
+    Base58,
+    /// BIP173 bech32, with the human readable part from `--hrp`
+    Bech32,
+}
+
+/// How entropy is encoded on each line
+#[derive(Debug, Args, Clone, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Style {
+    /// Encoding of both the input and output lines
+    #[clap(long, arg_enum, default_value = "words")]
+    pub format: Format,
+    /// Wordlist of the mnemonics
+    #[clap(long, default_value = "english", parse(try_from_str = parse_language))]
+    pub language: Language,
+    /// Prefix each word with its position, like `1:abandon 2:ability`
+    ///
+    /// The numbering is stripped again when reading
+    #[clap(long)]
+    pub numbered: bool,
+    /// Pad words to the width of the longest one in the wordlist, so shares line up in columns
+    ///
+    /// The padding is just more whitespace between words when reading
+    #[clap(long)]
+    pub align: bool,
+    /// Human readable part written in front of bech32 lines
+    #[clap(long, default_value = "xoriaz")]
+    pub hrp: String,
+    /// Byte order of hex lines
+    ///
+    /// The other formats always keep the canonical BIP39 order
+    #[clap(long, arg_enum, default_value = "big")]
+    pub endian: Endian,
+    /// Write hex lines in uppercase, for tools that only take that
+    ///
+    /// Either case is accepted when reading
+    #[clap(long)]
+    pub entropy_hex_uppercase: bool,
+    /// Letter case of written mnemonics, upper can be easier to read on paper
+    ///
+    /// Either is accepted when reading
+    #[clap(long, arg_enum, default_value = "lower")]
+    pub mnemonic_case: Case,
+    /// What goes between the words of a mnemonic, like commas for a row of CSV cells
+    ///
+    /// It's read back as spaces, except newlines, which only work for writing
+    #[clap(long, arg_enum, default_value = "space")]
+    pub word_separator: Separator,
+    /// Start each line with its 1-based position and a tab, like `7\tabandon ...`
+    ///
+    /// Reading checks the positions, to catch deleted or reordered lines in a share.
+    /// The secret that `split` reads doesn't need them
+    #[clap(long)]
+    pub index: bool,
+    /// Entropy in each line, for 12, 18 or 24 word mnemonics
+    #[clap(long, default_value = "256", possible_values = &["128", "192", "256"])]
+    pub bits: usize,
+    /// Only accept lines written exactly as they would be written, down to every space
+    ///
+    /// That leaves out trimming, other spacing and letter case, and other Unicode forms
+    #[clap(long)]
+    pub strict_whitespace: bool,
+}
+
+impl Default for Style {
+    /// The same as the command line defaults
+    fn default() -> Self {
+        Self {
+            format: Format::Words,
+            language: Language::English,
+            numbered: false,
+            align: false,
+            hrp: "xoriaz".into(),
+            endian: Endian::Big,
+            entropy_hex_uppercase: false,
+            mnemonic_case: Case::Lower,
+            word_separator: Separator::Space,
+            index: false,
+            bits: 256,
+            strict_whitespace: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum Endian {
+    /// The order BIP39 uses
+    Big,
+    /// Reversed
+    Little,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum Case {
+    /// As in the wordlists
+    Lower,
+    /// Where the script has it at all
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum Separator {
+    Space,
+    Comma,
+    Semicolon,
+    Tab,
+    /// Each word on a line of its own, which can't be read back line by line
+    Newline,
+}
+
+impl Separator {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Space => " ",
+            Self::Comma => ",",
+            Self::Semicolon => ";",
+            Self::Tab => "\t",
+            Self::Newline => "\n",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Mnemonic(bip39::Error),
+    /// The mnemonic didn't parse and also wasn't in the wordlists' normal form
+    Unnormalized(bip39::Error),
+    Invalid(Format, &'static str),
+    /// The expected `--index` and the line's actual start
+    Index(usize, String),
+    /// The line decoded, but isn't written the way `--strict-whitespace` wants
+    NotCanonical,
+    /// The line didn't decode, but would have in the other format, to as many bytes
+    LooksLike(Format, Box<Self>),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Mnemonic(err) => err.fmt(f),
+            Self::Unnormalized(err) => write!(
+                f,
+                "{err}, and the line isn't in the NFKD Unicode form of the wordlists; \
+                 if the file went through a tool that renormalizes text, like macOS does, \
+                 check that it wasn't mangled on the way"
+            ),
+            Self::Invalid(format, why) => write!(f, "invalid {format:?}: {why}"),
+            Self::Index(n, found) => write!(
+                f,
+                "expected index {n} but the line starts with `{found}`, \
+                 lines have gone missing or changed places"
+            ),
+            Self::NotCanonical => f.write_str(
+                "the line isn't written exactly the way it would be, \
+                 look for stray or doubled whitespace, a carriage return, or a different case",
+            ),
+            Self::LooksLike(other, err) => {
+                let name = other.to_possible_value().expect("not skipped");
+                write!(
+                    f,
+                    "{err}, but the line looks like {other:?}, did you mean --format {}?",
+                    name.get_name()
+                )
+            }
+        }
+    }
+}
+
+/// Name of `language` on the command line
+#[must_use]
+pub const fn language_name(language: Language) -> &'static str {
+    match language {
+        Language::English => "english",
+        #[cfg(feature = "chinese-simplified")]
+        Language::SimplifiedChinese => "chinese-simplified",
+        #[cfg(feature = "chinese-traditional")]
+        Language::TraditionalChinese => "chinese-traditional",
+        #[cfg(feature = "czech")]
+        Language::Czech => "czech",
+        #[cfg(feature = "french")]
+        Language::French => "french",
+        #[cfg(feature = "italian")]
+        Language::Italian => "italian",
+        #[cfg(feature = "japanese")]
+        Language::Japanese => "japanese",
+        #[cfg(feature = "korean")]
+        Language::Korean => "korean",
+        #[cfg(feature = "spanish")]
+        Language::Spanish => "spanish",
+    }
+}
+
+/// The language called `name` on the command line
+///
+/// # Errors
+///
+/// If this build doesn't have it
+pub fn parse_language(name: &str) -> Result<Language, String> {
+    Language::all()
+        .iter()
+        .copied()
+        .find(|&l| language_name(l) == name)
+        .ok_or_else(|| format!("{name} isn't one of the languages in this build"))
+}
+
+impl Style {
+    /// Width of a line in bytes
+    #[must_use]
+    pub const fn bytes(&self) -> usize {
+        self.bits / 8
+    }
+    /// The entropy of a single mnemonic given on the command line
+    ///
+    /// # Panics
+    ///
+    /// If `words` isn't one, without repeating them
+    #[must_use]
+    pub fn parse_mnemonic(&self, option: &str, words: &str) -> Entropy {
+        match parse_words(self.language, words) {
+            Ok(m) => m.to_entropy().into(),
+            Err(err) => panic!(
+                "{option} isn't a mnemonic in {}: {err}",
+                language_name(self.language)
+            ),
+        }
+    }
+    /// Decode `line` forgiving stray whitespace, as without `--strict-whitespace`
+    fn decode_leniently(&self, line: &str, n: usize) -> Result<Entropy, Error> {
+        let mut line = line.trim();
+        if self.index {
+            let (index, rest) = line.split_once('\t').unwrap_or(("", line));
+            if index.parse() != Ok(n) {
+                return Err(Error::Index(n, index.chars().take(20).collect()));
+            }
+            line = rest.trim_start();
+        }
+        self.decode_as(self.format, line).map_err(|err| {
+            let other = Format::value_variants().iter().copied().find(|&other| {
+                other != self.format
+                    && self
+                        .decode_as(other, line)
+                        .is_ok_and(|entropy| entropy.len() == self.bytes())
+            });
+            match other {
+                Some(other) => Error::LooksLike(other, Box::new(err)),
+                None => err,
+            }
+        })
+    }
+    fn decode_as(&self, format: Format, line: &str) -> Result<Entropy, Error> {
+        match format {
+            Format::Words => match self.word_separator {
+                Separator::Space => Ok(parse_words(self.language, line)?.to_entropy().into()),
+                Separator::Newline => Err(Error::Invalid(
+                    format,
+                    "a word on each line can't be read back, only written",
+                )),
+                separator => {
+                    let line = line.replace(separator.as_str(), " ");
+                    Ok(parse_words(self.language, &line)?.to_entropy().into())
+                }
+            },
+            Format::Hex => hex_decode(line).map(|mut e| {
+                if self.endian == Endian::Little {
+                    e.reverse();
+                }
+                e
+            }),
+            Format::Base58 => base58_decode(line),
+            Format::Bech32 => bech32_decode(line),
+        }
+    }
+    /// Characters in a column of `--align`ed lines of `words` words, before the space between
+    fn column_width(&self, words: usize) -> usize {
+        let list = self.language.words_by_prefix("");
+        let longest = list.iter().map(|w| w.chars().count()).max().unwrap_or(0);
+        if self.numbered {
+            longest + words.to_string().len() + 1
+        } else {
+            longest
+        }
+    }
+    /// Average length of a written line holding `bytes` of entropy, newline included
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn line_len(&self, bytes: usize) -> f64 {
+        match self.format {
+            Format::Words => {
+                let list = self.language.words_by_prefix("");
+                let avg = list.iter().map(|w| w.len()).sum::<usize>() as f64 / list.len() as f64;
+                let words = bytes * 3 / 4;
+                let numbering = if self.numbered {
+                    (1..=words).map(|n| n.to_string().len() + 1).sum()
+                } else {
+                    0
+                };
+                (words as f64).mul_add(avg, (words + numbering) as f64)
+            }
+            Format::Hex => (bytes * 2 + 1) as f64,
+            Format::Base58 => (bytes as f64 * 256f64.ln() / 58f64.ln()).ceil() + 1.,
+            Format::Bech32 => (self.hrp.len() + 1 + (bytes * 8).div_ceil(5) + 6 + 1) as f64,
+        }
+    }
+}
+
+/// How lines turn into entropy and back, which generating, splitting and combining are generic over
+///
+/// [`Style`] is what the command line options pick, another encoding only has to implement this
+pub trait Encoding {
+    /// Only the meaningful bytes of `line`, whatever its width
+    ///
+    /// `n` is the 1-based position of the line in its file
+    ///
+    /// # Errors
+    ///
+    /// If `line` isn't written this way
+    fn decode(&self, line: &str, n: usize) -> Result<Entropy, Error>;
+    /// [`decode`](Self::decode) a line of a secret being split, which may be plain
+    /// even where its shares are to be written differently
+    ///
+    /// # Errors
+    ///
+    /// As for [`decode`](Self::decode)
+    fn decode_secret(&self, line: &str, n: usize) -> Result<Entropy, Error> {
+        self.decode(line, n)
+    }
+    /// Write `entropy` as the line at 1-based position `n`, followed by a newline
+    ///
+    /// # Errors
+    ///
+    /// If `w` fails
+    fn write(&self, w: impl Write, n: usize, entropy: &[u8]) -> std::io::Result<()>;
+    /// How many newlines [`write`](Self::write) puts into a written entry
+    ///
+    /// Anything but 1 can't be read back a line at a time
+    fn lines_per_entry(&self) -> usize {
+        1
+    }
+}
+
+impl Encoding for Style {
+    fn decode(&self, line: &str, n: usize) -> Result<Entropy, Error> {
+        let entropy = self.decode_leniently(line, n)?;
+        if self.strict_whitespace {
+            let mut canonical = Vec::new();
+            self.write(&mut canonical, n, &entropy)
+                .expect("writing to memory");
+            let line = line.strip_suffix('\n').unwrap_or(line);
+            if canonical.strip_suffix(b"\n") != Some(line.as_bytes()) {
+                return Err(Error::NotCanonical);
+            }
+        }
+        Ok(entropy)
+    }
+    /// Lines of a secret may leave out `--index`, the ones that have it still get it checked
+    fn decode_secret(&self, line: &str, n: usize) -> Result<Entropy, Error> {
+        let indexed = line
+            .trim_start()
+            .split_once('\t')
+            .is_some_and(|(index, _)| {
+                !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit())
+            });
+        if self.index && !indexed {
+            return Self {
+                index: false,
+                ..self.clone()
+            }
+            .decode(line, n);
+        }
+        self.decode(line, n)
+    }
+    fn write(&self, mut w: impl Write, n: usize, entropy: &[u8]) -> std::io::Result<()> {
+        if self.index {
+            write!(w, "{n}\t")?;
+        }
+        match self.format {
+            Format::Words => {
+                let m = Mnemonic::from_entropy_in(self.language, entropy).unwrap();
+                if !self.numbered
+                    && !self.align
+                    && self.mnemonic_case == Case::Lower
+                    && self.word_separator == Separator::Space
+                {
+                    return writeln!(w, "{m}");
+                }
+                let words = m.word_count();
+                let column = if self.align {
+                    self.column_width(words)
+                } else {
+                    0
+                };
+                for (k, word) in m.word_iter().enumerate() {
+                    if k != 0 {
+                        w.write_all(self.word_separator.as_str().as_bytes())?;
+                    }
+                    let mut len = word.chars().count();
+                    if self.numbered {
+                        let position = format!("{}:", k + 1);
+                        w.write_all(position.as_bytes())?;
+                        len += position.len();
+                    }
+                    match self.mnemonic_case {
+                        Case::Lower => w.write_all(word.as_bytes())?,
+                        Case::Upper => w.write_all(word.to_uppercase().as_bytes())?,
+                    }
+                    if k + 1 < words {
+                        for _ in len..column {
+                            w.write_all(b" ")?;
+                        }
+                    }
+                }
+                writeln!(w)
+            }
+            Format::Hex => {
+                let upper = self.entropy_hex_uppercase;
+                let mut write_byte = |b| {
+                    if upper {
+                        write!(w, "{b:02X}")
+                    } else {
+                        write!(w, "{b:02x}")
+                    }
+                };
+                match self.endian {
+                    Endian::Big => entropy.iter().try_for_each(&mut write_byte)?,
+                    Endian::Little => entropy.iter().rev().try_for_each(&mut write_byte)?,
+                }
+                writeln!(w)
+            }
+            Format::Base58 => writeln!(w, "{}", base58_encode(entropy)),
+            Format::Bech32 => writeln!(w, "{}", bech32_encode(&self.hrp, entropy)),
+        }
+    }
+    fn lines_per_entry(&self) -> usize {
+        match (self.format, self.word_separator) {
+            (Format::Words, Separator::Newline) => self.bytes() * 3 / 4,
+            _ => 1,
+        }
+    }
+}
+
+/// Parse a line of words, ignoring any `--numbered` position prefixes
+///
+/// Words are matched case-insensitively and in any Unicode normalization form
+fn parse_words(language: Language, line: &str) -> Result<Mnemonic, Error> {
+    parse_normalized_words(language, line).map_err(|err| {
+        if is_nfkd_quick(line.chars()) == IsNormalized::Yes {
+            Error::Mnemonic(err)
+        } else {
+            Error::Unnormalized(err)
+        }
+    })
+}
+
+fn parse_normalized_words(language: Language, line: &str) -> Result<Mnemonic, bip39::Error> {
+    let lower;
+    let line = if line.chars().any(char::is_uppercase) {
+        lower = line.to_lowercase();
+        &lower
+    } else {
+        line
+    };
+    if !line.contains(':') {
+        return Mnemonic::parse_in(language, line);
+    }
+    let words: SmallVec<[&str; 24]> = line
+        .split_whitespace()
+        .map(|word| match word.split_once(':') {
+            Some((n, word)) if n.bytes().all(|b| b.is_ascii_digit()) => word,
+            _ => word,
+        })
+        .collect();
+    Mnemonic::parse_in(language, words.join(" "))
+}
+
+fn push(out: &mut Entropy, format: Format, b: u8) -> Result<(), Error> {
+    if out.len() == out.inline_size() {
+        return Err(Error::Invalid(format, "longer than 32 bytes"));
+    }
+    out.push(b);
+    Ok(())
+}
+
+fn hex_decode(line: &str) -> Result<Entropy, Error> {
+    const fn nibble(c: u8) -> Option<u8> {
+        match c {
+            b'0'..=b'9' => Some(c - b'0'),
+            b'a'..=b'f' => Some(c - b'a' + 10),
+            b'A'..=b'F' => Some(c - b'A' + 10),
+            _ => None,
+        }
+    }
+    let bytes = line.as_bytes();
+    if !bytes.len().is_multiple_of(2) {
+        return Err(Error::Invalid(Format::Hex, "odd number of digits"));
+    }
+    let mut out = Entropy::new();
+    for pair in bytes.chunks_exact(2) {
+        match (nibble(pair[0]), nibble(pair[1])) {
+            (Some(hi), Some(lo)) => push(&mut out, Format::Hex, hi << 4 | lo)?,
+            _ => return Err(Error::Invalid(Format::Hex, "not a hex digit")),
+        }
+    }
+    Ok(out)
+}
+
+const BASE58: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn base58_encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    // little-endian base58 digits of the non-zero-prefixed number
+    let mut digits: SmallVec<[u8; 45]> = SmallVec::new();
+    for &b in &bytes[zeros..] {
+        let mut carry = u32::from(b);
+        for d in &mut digits {
+            carry += u32::from(*d) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry != 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    std::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|&d| char::from(BASE58[usize::from(d)])),
+        )
+        .collect()
+}
+
+fn base58_decode(line: &str) -> Result<Entropy, Error> {
+    let zeros = line.bytes().take_while(|&c| c == b'1').count();
+    // little-endian bytes of the number after the leading ones
+    let mut bytes = Entropy::new();
+    for c in line.bytes().skip(zeros) {
+        let mut carry = BASE58
+            .iter()
+            .position(|&a| a == c)
+            .ok_or(Error::Invalid(Format::Base58, "not a base58 digit"))?
+            as u32;
+        for b in &mut bytes {
+            carry += u32::from(*b) * 58;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry != 0 {
+            push(&mut bytes, Format::Base58, carry as u8)?;
+            carry >>= 8;
+        }
+    }
+    let mut out = Entropy::new();
+    for _ in 0..zeros {
+        push(&mut out, Format::Base58, 0)?;
+    }
+    for &b in bytes.iter().rev() {
+        push(&mut out, Format::Base58, b)?;
+    }
+    Ok(out)
+}
+
+const BECH32: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn bech32_polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GEN: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    values.fold(1, |chk, v| {
+        let top = chk >> 25;
+        let chk = (chk & 0x01ff_ffff) << 5 ^ u32::from(v);
+        GEN.iter()
+            .enumerate()
+            .filter(|(i, _)| top >> i & 1 == 1)
+            .fold(chk, |chk, (_, g)| chk ^ g)
+    })
+}
+
+fn bech32_hrp_expand(hrp: &str) -> impl Iterator<Item = u8> + '_ {
+    hrp.bytes()
+        .map(|c| c >> 5)
+        .chain(std::iter::once(0))
+        .chain(hrp.bytes().map(|c| c & 31))
+}
+
+fn bech32_encode(hrp: &str, bytes: &[u8]) -> String {
+    let mut data: SmallVec<[u8; 58]> = SmallVec::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for &b in bytes {
+        acc = acc << 8 | u32::from(b);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            data.push((acc >> bits & 31) as u8);
+        }
+    }
+    if bits != 0 {
+        data.push((acc << (5 - bits) & 31) as u8);
+    }
+    let chk = bech32_polymod(
+        bech32_hrp_expand(hrp)
+            .chain(data.iter().copied())
+            .chain([0; 6]),
+    ) ^ 1;
+    data.extend((0..6).map(|i| (chk >> (5 * (5 - i)) & 31) as u8));
+    let mut out = String::with_capacity(hrp.len() + 1 + data.len());
+    out.push_str(hrp);
+    out.push('1');
+    out.extend(data.iter().map(|&d| char::from(BECH32[usize::from(d)])));
+    out
+}
+
+fn bech32_decode(line: &str) -> Result<Entropy, Error> {
+    let invalid = |why| Error::Invalid(Format::Bech32, why);
+    if line.bytes().any(|c| c.is_ascii_uppercase()) && line.bytes().any(|c| c.is_ascii_lowercase())
+    {
+        return Err(invalid("mixed case"));
+    }
+    let line = line.to_ascii_lowercase();
+    let (hrp, data) = line
+        .rsplit_once('1')
+        .ok_or_else(|| invalid("no separator"))?;
+    if hrp.is_empty() || data.len() < 6 {
+        return Err(invalid("too short"));
+    }
+    let data = data
+        .bytes()
+        .map(|c| BECH32.iter().position(|&a| a == c).map(|d| d as u8))
+        .collect::<Option<SmallVec<[u8; 64]>>>()
+        .ok_or_else(|| invalid("not a bech32 character"))?;
+    if bech32_polymod(bech32_hrp_expand(hrp).chain(data.iter().copied())) != 1 {
+        return Err(invalid("bad checksum"));
+    }
+    let mut out = Entropy::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for &d in &data[..data.len() - 6] {
+        acc = acc << 5 | u32::from(d);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            push(&mut out, Format::Bech32, (acc >> bits) as u8)?;
+        }
+    }
+    if bits >= 5 || acc & ((1 << bits) - 1) != 0 {
+        return Err(invalid("bad padding"));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uppercase_words_decode() {
+        let line = format!("{}ART", "ABANDON ".repeat(23));
+        let style = Style {
+            bits: 256,
+            ..Style::default()
+        };
+        assert_eq!(style.decode(&line, 1).unwrap()[..], [0; 32]);
+        let mixed = format!("{}About", "Abandon ".repeat(11));
+        let style = Style {
+            bits: 128,
+            ..Style::default()
+        };
+        assert_eq!(style.decode(&mixed, 1).unwrap()[..], [0; 16]);
+    }
+
+    #[test]
+    fn looks_like_only_lines_of_the_width() {
+        let style = Style::default();
+        let hex = "00".repeat(32);
+        let err = style.decode(&hex, 1).unwrap_err();
+        assert!(matches!(err, Error::LooksLike(Format::Hex, _)), "{err:?}");
+        for junk in ["deadbeef", "abc", &"00".repeat(16)] {
+            let err = style.decode(junk, 1).unwrap_err();
+            assert!(!matches!(err, Error::LooksLike(..)), "{junk}: {err:?}");
+        }
+    }
+
+    #[cfg(feature = "japanese")]
+    mod japanese {
+        use unicode_normalization::UnicodeNormalization;
+
+        use super::*;
+
+        fn style() -> Style {
+            Style {
+                bits: 128,
+                language: Language::Japanese,
+                ..Style::default()
+            }
+        }
+
+        #[test]
+        fn nfd_and_nfc_decode() {
+            let mut line = Vec::new();
+            style().write(&mut line, 1, &[0; 16]).unwrap();
+            let line = String::from_utf8(line).unwrap();
+            let nfd: String = line.nfd().collect();
+            let nfc: String = line.nfc().collect();
+            assert_ne!(nfc, nfd);
+            assert_eq!(style().decode(&nfd, 1).unwrap()[..], [0; 16]);
+            assert_eq!(style().decode(&nfc, 1).unwrap()[..], [0; 16]);
+        }
+
+        #[test]
+        fn unnormalized_typo_is_explained() {
+            let line: String = format!("{}あおぞぞ", "あいこくしん ".repeat(11))
+                .nfc()
+                .collect();
+            let err = style().decode(&line, 1).unwrap_err();
+            assert!(matches!(err, Error::Unnormalized(_)), "{err:?}");
+            assert!(err.to_string().contains("isn't in the NFKD Unicode form"));
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! All shares of a split interleaved on one stream, for pipelines that route them on
+//!
+//! Every line is a frame: the 0-based share index and the length of the line,
+//! both as big-endian `u32`s, then the line itself with its newline.
+//! `split --framed` writes them, and `unframe` sorts them back into files.
+
+use clap::Args;
+
+use std::{
+    cell::RefCell,
+    io::{stdin, Read, Write},
+    path::PathBuf,
+};
+
+use crate::{create_files, finish_file, Output};
+
+/// One share's lines, written as frames to a stream shared with the other shares
+pub struct FrameWriter<'a, W: Write> {
+    out: &'a RefCell<W>,
+    index: u32,
+    line: Vec<u8>,
+}
+
+impl<'a, W: Write> FrameWriter<'a, W> {
+    pub fn new(out: &'a RefCell<W>, index: usize) -> Self {
+        Self {
+            out,
+            index: index.try_into().expect("too many shares to frame"),
+            line: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> Write for FrameWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for &b in buf {
+            self.line.push(b);
+            if b == b'\n' {
+                let len = u32::try_from(self.line.len()).expect("lines are short");
+                let mut out = self.out.borrow_mut();
+                out.write_all(&self.index.to_be_bytes())?;
+                out.write_all(&len.to_be_bytes())?;
+                out.write_all(&self.line)?;
+                self.line.clear();
+            }
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.borrow_mut().flush()
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Unframe {
+    /// Where each share goes, in the order of their index
+    #[clap(required = true, parse(from_os_str))]
+    dest: Vec<PathBuf>,
+    #[clap(flatten)]
+    output: Output,
+}
+
+/// Read frames from standard input into the share files they belong to
+pub fn run(Unframe { dest, output }: Unframe) {
+    let mut files: Vec<_> = create_files(&dest, output)
+        .into_iter()
+        .map(std::io::BufWriter::new)
+        .collect();
+    let mut input = stdin().lock();
+    let mut header = [0; 8];
+    let mut line = Vec::new();
+    loop {
+        match input.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(err) => panic!("couldn't read: {err}"),
+        }
+        let (index, len) = header.split_at(4);
+        let index = u32::from_be_bytes(index.try_into().unwrap()) as usize;
+        let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+        let file = files.get_mut(index).unwrap_or_else(|| {
+            panic!(
+                "a frame is for share {index}, but only {} DESTs were given",
+                dest.len()
+            )
+        });
+        line.resize(len, 0);
+        input
+            .read_exact(&mut line)
+            .unwrap_or_else(|err| panic!("a frame of share {index} was cut off: {err}"));
+        file.write_all(&line)
+            .unwrap_or_else(|err| panic!("couldn't write: {err}"));
+    }
+    drop(input);
+    for file in files {
+        finish_file(file);
+    }
+}
@@ -0,0 +1,404 @@
+//! Reading gzip-compressed sources, told apart from plain ones by their first two bytes
+//!
+//! Sources are only ever decompressed, so this is just an inflater after RFC 1951 and 1952,
+//! decoding one bit at a time. Concatenated members are read one after another.
+
+// values read as a few bits, and the size and CRC, which are only compared modulo 2^32
+#![allow(clippy::cast_possible_truncation)]
+
+use std::io::{BufRead, Error, ErrorKind, Read, Result};
+
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// How far back a match can reach
+const WINDOW: usize = 1 << 15;
+/// How much to decode at once
+const CHUNK: usize = 1 << 14;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order the code length code lengths of a dynamic block come in
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 0 {
+                c >> 1
+            } else {
+                0xedb8_8320 ^ (c >> 1)
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+};
+
+fn corrupt(why: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("corrupt gzip: {why}"))
+}
+
+/// Whether `input` starts like a gzip file, without consuming anything
+pub fn is_gzip(input: &mut impl BufRead) -> bool {
+    input
+        .fill_buf()
+        .is_ok_and(|start| start.starts_with(&MAGIC))
+}
+
+/// A canonical Huffman code, as the number of codes of each length and the symbols in code order
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self> {
+        let mut counts = [0; 16];
+        for &len in lengths {
+            counts[usize::from(len)] += 1;
+        }
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(corrupt("over-subscribed code"));
+            }
+        }
+        let mut offsets = [0; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in (0..).zip(lengths) {
+            if len != 0 {
+                symbols[usize::from(offsets[usize::from(len)])] = symbol;
+                offsets[usize::from(len)] += 1;
+            }
+        }
+        Ok(Self { counts, symbols })
+    }
+
+    fn fixed() -> (Self, Self) {
+        let mut lengths = [8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        let literals = Self::new(&lengths).expect("complete");
+        let distances = Self::new(&[5; 30]).expect("complete");
+        (literals, distances)
+    }
+}
+
+/// Bits of the compressed stream, least significant first
+struct Bits<R> {
+    input: R,
+    buf: u32,
+    len: u32,
+}
+
+impl<R: BufRead> Bits<R> {
+    fn byte(&mut self) -> Result<u8> {
+        let byte = *self
+            .input
+            .fill_buf()?
+            .first()
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "gzip stream is cut off"))?;
+        self.input.consume(1);
+        Ok(byte)
+    }
+
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        while self.len < n {
+            self.buf |= u32::from(self.byte()?) << self.len;
+            self.len += 8;
+        }
+        let value = self.buf & ((1 << n) - 1);
+        self.buf >>= n;
+        self.len -= n;
+        Ok(value)
+    }
+
+    /// Drop the rest of the current byte
+    const fn align(&mut self) {
+        self.buf = 0;
+        self.len = 0;
+    }
+
+    fn u16_le(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes([self.byte()?, self.byte()?]))
+    }
+
+    fn u32_le(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes([
+            self.byte()?,
+            self.byte()?,
+            self.byte()?,
+            self.byte()?,
+        ]))
+    }
+
+    fn decode(&mut self, code: &Huffman) -> Result<u16> {
+        let (mut bits, mut first, mut index) = (0, 0, 0);
+        for &count in &code.counts[1..] {
+            bits |= self.bits(1)?;
+            let count = u32::from(count);
+            if bits < first + count {
+                return Ok(code.symbols[(index + bits - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            bits <<= 1;
+        }
+        Err(corrupt("invalid code"))
+    }
+}
+
+enum State {
+    /// Before a member's header, or at the end of the stream
+    Member,
+    /// Before a block's header
+    Block,
+    /// In a stored block, with this many bytes left
+    Stored(u16),
+    Codes(Huffman, Huffman),
+    Done,
+}
+
+/// Decompressed bytes of the current member, and the ones that haven't been read yet
+struct Sink {
+    window: Vec<u8>,
+    written: usize,
+    crc: u32,
+    out: Vec<u8>,
+    pos: usize,
+}
+
+impl Sink {
+    fn put(&mut self, byte: u8) {
+        self.window[self.written % WINDOW] = byte;
+        self.written += 1;
+        self.crc = CRC_TABLE[usize::from((self.crc as u8) ^ byte)] ^ (self.crc >> 8);
+        self.out.push(byte);
+    }
+}
+
+/// The decompressed contents of a gzip stream
+pub struct Decoder<R> {
+    bits: Bits<R>,
+    state: State,
+    last_block: bool,
+    members: usize,
+    sink: Sink,
+}
+
+impl<R: BufRead> Decoder<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            bits: Bits {
+                input,
+                buf: 0,
+                len: 0,
+            },
+            state: State::Member,
+            last_block: false,
+            members: 0,
+            sink: Sink {
+                window: vec![0; WINDOW],
+                written: 0,
+                crc: !0,
+                out: Vec::with_capacity(CHUNK + 258),
+                pos: 0,
+            },
+        }
+    }
+
+    fn member(&mut self) -> Result<()> {
+        if self.members != 0 && self.bits.input.fill_buf()?.is_empty() {
+            self.state = State::Done;
+            return Ok(());
+        }
+        let mut header = [0; 10];
+        for b in &mut header {
+            *b = self.bits.byte()?;
+        }
+        let flags = header[3];
+        if header[..2] != MAGIC || header[2] != 8 || flags & 0xe0 != 0 {
+            return Err(corrupt("not a deflate member"));
+        }
+        if flags & 4 != 0 {
+            for _ in 0..self.bits.u16_le()? {
+                self.bits.byte()?;
+            }
+        }
+        // file name and comment
+        for flag in [8, 16] {
+            if flags & flag != 0 {
+                while self.bits.byte()? != 0 {}
+            }
+        }
+        if flags & 2 != 0 {
+            self.bits.u16_le()?;
+        }
+        self.members += 1;
+        self.sink.written = 0;
+        self.sink.crc = !0;
+        self.state = State::Block;
+        Ok(())
+    }
+
+    fn block(&mut self) -> Result<()> {
+        if self.last_block {
+            self.bits.align();
+            let (crc, size) = (self.bits.u32_le()?, self.bits.u32_le()?);
+            if crc != !self.sink.crc || size != self.sink.written as u32 {
+                return Err(corrupt("the checksum doesn't match"));
+            }
+            self.last_block = false;
+            self.state = State::Member;
+            return Ok(());
+        }
+        self.last_block = self.bits.bits(1)? == 1;
+        self.state = match self.bits.bits(2)? {
+            0 => {
+                self.bits.align();
+                let len = self.bits.u16_le()?;
+                if self.bits.u16_le()? != !len {
+                    return Err(corrupt("stored block length doesn't match"));
+                }
+                State::Stored(len)
+            }
+            1 => {
+                let (literals, distances) = Huffman::fixed();
+                State::Codes(literals, distances)
+            }
+            2 => self.dynamic()?,
+            _ => return Err(corrupt("invalid block type")),
+        };
+        Ok(())
+    }
+
+    fn dynamic(&mut self) -> Result<State> {
+        let literals = self.bits.bits(5)? as usize + 257;
+        let distances = self.bits.bits(5)? as usize + 1;
+        let mut lengths = [0; 19];
+        for &i in &CODE_LENGTH_ORDER[..self.bits.bits(4)? as usize + 4] {
+            lengths[i] = self.bits.bits(3)? as u8;
+        }
+        let code = Huffman::new(&lengths)?;
+        let mut lengths = Vec::with_capacity(literals + distances);
+        while lengths.len() < literals + distances {
+            let (len, repeat) = match self.bits.decode(&code)? {
+                len @ 0..=15 => (len as u8, 1),
+                16 => {
+                    let previous = *lengths.last().ok_or_else(|| corrupt("nothing to repeat"))?;
+                    (previous, 3 + self.bits.bits(2)?)
+                }
+                17 => (0, 3 + self.bits.bits(3)?),
+                _ => (0, 11 + self.bits.bits(7)?),
+            };
+            if lengths.len() + repeat as usize > literals + distances {
+                return Err(corrupt("too many code lengths"));
+            }
+            lengths.extend(std::iter::repeat_n(len, repeat as usize));
+        }
+        if lengths[256] == 0 {
+            return Err(corrupt("no end of block code"));
+        }
+        Ok(State::Codes(
+            Huffman::new(&lengths[..literals])?,
+            Huffman::new(&lengths[literals..])?,
+        ))
+    }
+
+    /// Decode until there's a chunk of output or the stream ends
+    fn step(&mut self) -> Result<()> {
+        while self.sink.out.len() < CHUNK {
+            match &mut self.state {
+                State::Member => self.member()?,
+                State::Block => self.block()?,
+                State::Stored(0) => self.state = State::Block,
+                State::Stored(left) => {
+                    *left -= 1;
+                    let byte = self.bits.byte()?;
+                    self.sink.put(byte);
+                }
+                State::Codes(literals, distances) => {
+                    let symbol = usize::from(self.bits.decode(literals)?);
+                    if symbol < 256 {
+                        self.sink.put(symbol as u8);
+                        continue;
+                    }
+                    if symbol == 256 {
+                        self.state = State::Block;
+                        continue;
+                    }
+                    let i = symbol - 257;
+                    let (Some(&base), Some(&extra)) = (LENGTH_BASE.get(i), LENGTH_EXTRA.get(i))
+                    else {
+                        return Err(corrupt("invalid length"));
+                    };
+                    let len = usize::from(base) + self.bits.bits(extra.into())? as usize;
+                    let i = usize::from(self.bits.decode(distances)?);
+                    let (Some(&base), Some(&extra)) = (DISTANCE_BASE.get(i), DISTANCE_EXTRA.get(i))
+                    else {
+                        return Err(corrupt("invalid distance"));
+                    };
+                    let distance = usize::from(base) + self.bits.bits(extra.into())? as usize;
+                    let sink = &mut self.sink;
+                    if distance > sink.written.min(WINDOW) {
+                        return Err(corrupt("distance too far back"));
+                    }
+                    for _ in 0..len {
+                        let byte = sink.window[(sink.written - distance) % WINDOW];
+                        sink.put(byte);
+                    }
+                }
+                State::Done => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+    fn read(&mut self, out: &mut [u8]) -> Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(out.len());
+        out[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Decoder<R> {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        if self.sink.pos == self.sink.out.len() {
+            self.sink.out.clear();
+            self.sink.pos = 0;
+            self.step()?;
+        }
+        Ok(&self.sink.out[self.sink.pos..])
+    }
+    fn consume(&mut self, n: usize) {
+        self.sink.pos += n;
+    }
+}
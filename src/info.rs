@@ -0,0 +1,113 @@
+//! A summary of a file, without printing any of its lines
+
+use bip39::Mnemonic;
+use clap::Args;
+
+use std::{collections::HashMap, num::NonZeroUsize, path::PathBuf};
+
+use crate::{
+    check_width,
+    error::Failure,
+    format::{Encoding, Style},
+    meta, open_input, read_data_line, Range,
+};
+
+#[derive(Debug, Args)]
+pub struct Info {
+    #[clap(parse(from_os_str))]
+    source: PathBuf,
+    /// Also count how often each word of the wordlist comes up
+    ///
+    /// For random lines most words are used about equally often,
+    /// so a long tail on either side points at a broken generator
+    #[clap(long)]
+    count_distinct_words: bool,
+    /// Only decode every Kth line, starting with the first, for a quick look at a huge file
+    ///
+    /// Lines that don't decode are then counted instead of stopping everything
+    #[clap(long, value_name = "K")]
+    every_nth: Option<NonZeroUsize>,
+    #[clap(flatten)]
+    style: Style,
+}
+
+pub fn run(
+    Info {
+        source,
+        count_distinct_words,
+        every_nth,
+        style,
+    }: Info,
+) {
+    let mut range = Range::default();
+    let style = meta::configure(std::slice::from_ref(&source), style, &mut range);
+    let mut file = open_input(&source);
+    let mut words: HashMap<&'static str, usize> = HashMap::new();
+    let str = &mut String::new();
+    let (mut i, mut checked, mut invalid) = (0, 0, 0);
+    while read_data_line(&mut file, str, None, i) != 0 {
+        if every_nth.is_some_and(|k| !i.is_multiple_of(k.get())) {
+            str.clear();
+            i += 1;
+            continue;
+        }
+        checked += 1;
+        let decoded = style.decode(str, i + 1);
+        str.clear();
+        let entropy = match decoded {
+            Ok(entropy) if entropy.len() == style.bytes() || every_nth.is_none() => entropy,
+            Err(e) if every_nth.is_none() => Failure::new("parse", e).line(i).raise(),
+            _ => {
+                invalid += 1;
+                i += 1;
+                continue;
+            }
+        };
+        check_width(&entropy, style.bytes(), None, i);
+        i += 1;
+        if count_distinct_words {
+            let mnemonic =
+                Mnemonic::from_entropy_in(style.language, &entropy).expect("checked width");
+            for word in mnemonic.word_iter() {
+                *words.entry(word).or_default() += 1;
+            }
+        }
+    }
+    range.finish(i);
+    println!("lines: {i}");
+    println!("format: {:?}", style.format);
+    println!("bits per line: {}", style.bits);
+    if let Some(k) = every_nth {
+        println!("sampled: 1 in {k} lines from the first, {checked} of {i}, not all of them");
+        println!(
+            "valid among the sampled: {} of {checked}",
+            checked - invalid
+        );
+    }
+    if count_distinct_words {
+        print_word_counts(&words, (checked - invalid) * style.bytes() * 3 / 4);
+    }
+}
+
+/// Print how many words of the wordlist came up how many times, out of `total` words
+// all counts are far below 2^52
+#[allow(clippy::cast_precision_loss)]
+fn print_word_counts(words: &HashMap<&'static str, usize>, total: usize) {
+    let listed = 2048;
+    println!("distinct words: {} of {listed}", words.len());
+    println!(
+        "expected uses per word: {:.1}",
+        total as f64 / listed as f64
+    );
+    let mut histogram = vec![listed - words.len()];
+    for &count in words.values() {
+        if histogram.len() <= count {
+            histogram.resize(count + 1, 0);
+        }
+        histogram[count] += 1;
+    }
+    println!("words used N times:");
+    for (uses, &n) in histogram.iter().enumerate().filter(|(_, &n)| n != 0) {
+        println!("  {uses}: {n}");
+    }
+}
@@ -0,0 +1,185 @@
+//! All shares of a split as plain lines of one text stream, for when only one file can be moved
+//!
+//! Line `k` of the stream, counting from 0, is line `k / N` of share `k % N`:
+//! the first line of each share in order, then the second of each, and so on.
+//! `split --interleave` writes it, and `xor --deinterleave N` takes it apart again.
+
+use std::{
+    cell::RefCell,
+    io::{BufRead, Cursor, Read, Write},
+    path::Path,
+};
+
+use smallvec::SmallVec;
+
+use crate::{open_input, Input};
+
+/// The stream and the line each share is in the middle of
+pub struct Interleaver<W: Write> {
+    out: W,
+    lines: Vec<Vec<u8>>,
+}
+
+impl<W: Write> Interleaver<W> {
+    pub fn new(out: W, shares: usize) -> Self {
+        Self {
+            out,
+            lines: vec![Vec::new(); shares],
+        }
+    }
+}
+
+/// One share's lines, held until every share has its next one and then written in order
+///
+/// The shares have to be written in step, a line of each at a time, like `split` does
+pub struct ShareWriter<'a, W: Write> {
+    shared: &'a RefCell<Interleaver<W>>,
+    index: usize,
+}
+
+impl<'a, W: Write> ShareWriter<'a, W> {
+    pub const fn new(shared: &'a RefCell<Interleaver<W>>, index: usize) -> Self {
+        Self { shared, index }
+    }
+}
+
+impl<W: Write> Write for ShareWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut shared = self.shared.borrow_mut();
+        let Interleaver { out, lines } = &mut *shared;
+        lines[self.index].extend_from_slice(buf);
+        if lines.iter().all(|line| line.ends_with(b"\n")) {
+            for line in lines {
+                out.write_all(line)?;
+                line.fill(0);
+                line.clear();
+            }
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.shared.borrow_mut().out.flush()
+    }
+}
+
+/// Take the stream at `path` apart into the lines of its `shares` shares
+pub fn deinterleave(path: &Path, shares: usize) -> SmallVec<[Input; 4]> {
+    assert!(shares >= 2, "--deinterleave needs at least 2 shares");
+    let mut stream = open_input(path);
+    let mut split = vec![Vec::new(); shares];
+    for k in 0.. {
+        if stream
+            .read_until(b'\n', &mut split[k % shares])
+            .unwrap_or_else(|err| panic!("couldn't read {}: {err}", path.display()))
+            == 0
+        {
+            break;
+        }
+    }
+    split
+        .into_iter()
+        .map(|lines| Box::new(Lines::new(lines)) as Input)
+        .collect()
+}
+
+/// The lines of one share, zeroed once they're done with
+pub struct Lines(Cursor<Vec<u8>>);
+
+impl Lines {
+    pub const fn new(lines: Vec<u8>) -> Self {
+        Self(Cursor::new(lines))
+    }
+}
+
+impl Read for Lines {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl BufRead for Lines {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.0.fill_buf()
+    }
+    fn consume(&mut self, amt: usize) {
+        self.0.consume(amt);
+    }
+}
+
+impl Drop for Lines {
+    fn drop(&mut self) {
+        self.0.get_mut().fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        format::{Encoding, Style},
+        tests::{failure, scratch},
+    };
+
+    /// The stream of `shares` shares of `lines` lines each, line `k` of share `s` being `s.k`,
+    /// written a piece of a line at a time
+    fn interleave(shares: usize, lines: usize) -> Vec<u8> {
+        let stream = RefCell::new(Interleaver::new(Vec::new(), shares));
+        let mut writers: Vec<_> = (0..shares).map(|s| ShareWriter::new(&stream, s)).collect();
+        for k in 0..lines {
+            for (s, w) in writers.iter_mut().enumerate() {
+                write!(w, "{s}.").unwrap();
+                writeln!(w, "{k}").unwrap();
+            }
+        }
+        drop(writers);
+        stream.into_inner().out
+    }
+
+    fn read_to_string(mut input: Input) -> String {
+        let mut out = String::new();
+        input.read_to_string(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn round_trip() {
+        let stream = interleave(3, 2);
+        assert_eq!(stream, b"0.0\n1.0\n2.0\n0.1\n1.1\n2.1\n");
+        let path = scratch("interleave");
+        std::fs::write(&path, &stream).unwrap();
+        let shares = deinterleave(&path, 3);
+        std::fs::remove_file(&path).unwrap();
+        let shares: Vec<_> = shares.into_iter().map(read_to_string).collect();
+        assert_eq!(shares, ["0.0\n0.1\n", "1.0\n1.1\n", "2.0\n2.1\n"]);
+    }
+
+    #[test]
+    fn uneven_final_block_fails_to_combine() {
+        let style = Style {
+            bits: 128,
+            ..Style::default()
+        };
+        let mut stream = Vec::new();
+        for (n, byte) in (1..=7).zip(0u8..) {
+            style.write(&mut stream, n, &[byte; 16]).unwrap();
+        }
+        let path = scratch("interleave-uneven");
+        std::fs::write(&path, &stream).unwrap();
+        let mut shares = deinterleave(&path, 3);
+        std::fs::remove_file(&path).unwrap();
+        let err = failure(|| {
+            crate::xor_inner::<16>(
+                Vec::new(),
+                &mut shares,
+                crate::Range::default(),
+                &style,
+                false,
+                None,
+                None,
+                None,
+                None,
+            );
+        });
+        assert_eq!((err.kind, err.file, err.line), ("length", Some(1), Some(2)));
+    }
+}
@@ -0,0 +1,31 @@
+//! Ctrl-C as a request to stop after the current line, for `gen --lines 0`
+//!
+//! Only the first one is caught, a second one ends the process right away as usual.
+//! Only on Linux, elsewhere Ctrl-C always ends it right away.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(target_os = "linux")]
+extern "C" fn on_sigint(_: libc::c_int) {
+    REQUESTED.store(true, Ordering::Relaxed);
+    // SAFETY: signal is async-signal-safe, and the default handler needs nothing set up
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+    }
+}
+
+/// Catch the next Ctrl-C, so [`requested`] tells about it instead of it ending the process
+pub fn catch() {
+    #[cfg(target_os = "linux")]
+    // SAFETY: the handler only stores to an atomic and resets itself
+    unsafe {
+        libc::signal(libc::SIGINT, on_sigint as *const () as libc::sighandler_t);
+    }
+}
+
+/// Whether Ctrl-C was pressed since [`catch`]
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
@@ -0,0 +1,12 @@
+//! The line formats and the XOR of `xoriaz`, for programs that build on them
+//!
+//! [`Encoding`] reads and writes the lines of a file, [`XorAccumulator`] puts them together.
+
+#![warn(clippy::pedantic, clippy::nursery)]
+
+pub mod accumulator;
+pub mod ct;
+pub mod format;
+
+pub use accumulator::XorAccumulator;
+pub use format::Encoding;
@@ -0,0 +1,93 @@
+//! Diagnostics on stderr for `--log-level`
+//!
+//! Only ever metadata like paths, line numbers and byte counts, never the lines themselves,
+//! except for the hidden `--dump-entropy` debugging aid.
+
+use bitcoin_hashes::hex::ToHex;
+use clap::ArgEnum;
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+};
+
+use crate::error::Failure;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
+pub enum Level {
+    /// Nothing
+    Off,
+    /// Files opened, created and removed
+    Info,
+    /// Also line counts and widths
+    Debug,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Off as u8);
+
+pub fn set(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+fn log(level: Level, args: fmt::Arguments) {
+    if LEVEL.load(Ordering::Relaxed) >= level as u8 {
+        eprintln!("{}: {args}", format!("{level:?}").to_lowercase());
+    }
+}
+
+pub fn info(args: fmt::Arguments) {
+    log(Level::Info, args);
+}
+
+pub fn debug(args: fmt::Arguments) {
+    log(Level::Debug, args);
+}
+
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+static FAIL_ON_WARNING: AtomicBool = AtomicBool::new(false);
+
+/// Make [`finish`] fail if there were any warnings, for `--fail-on-warning`
+pub fn fail_on_warning(fail: bool) {
+    FAIL_ON_WARNING.store(fail, Ordering::Relaxed);
+}
+
+/// Something that doesn't stop the run, but is likely wrong, printed whatever the `--log-level`
+pub fn warn(args: fmt::Arguments) {
+    WARNINGS.fetch_add(1, Ordering::Relaxed);
+    eprintln!("warning: {args}");
+}
+
+/// Fail after a run that warned, with `--fail-on-warning`
+pub fn finish() {
+    let warnings = WARNINGS.load(Ordering::Relaxed);
+    if warnings != 0 && FAIL_ON_WARNING.load(Ordering::Relaxed) {
+        Failure::new(
+            "warning",
+            format_args!("{warnings} warnings, which --fail-on-warning makes a failure"),
+        )
+        .raise();
+    }
+}
+
+/// How many lines `--dump-entropy` still prints
+static DUMP_LINES: AtomicUsize = AtomicUsize::new(0);
+
+/// Print the entropy of the first `lines` processed lines to stderr, as `--dump-entropy`
+pub fn dump_entropy(lines: usize) {
+    if lines != 0 {
+        warn(format_args!(
+            "--dump-entropy prints secret material to stderr, only use it for debugging"
+        ));
+    }
+    DUMP_LINES.store(lines, Ordering::Relaxed);
+}
+
+/// Whether the 0-based processed line `n` gets dumped
+pub fn dumping(n: usize) -> bool {
+    n < DUMP_LINES.load(Ordering::Relaxed)
+}
+
+/// Print `entropy` of the 0-based `line`
+pub fn entropy(line: usize, what: fmt::Arguments, entropy: &[u8]) {
+    eprintln!("entropy: line {} {what}: {}", line + 1, entropy.to_hex());
+}
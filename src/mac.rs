@@ -0,0 +1,173 @@
+//! Keyed hashes of whole share files, appended as a last `mac:` line
+//!
+//! `split --mac` adds them, and `xor --mac` and `check --mac` refuse shares
+//! that were corrupted or swapped before combining anything.
+//! Without `--mac` the line is just skipped.
+
+use bitcoin_hashes::{
+    hex::{FromHex, ToHex},
+    hmac::{Hmac, HmacEngine},
+    sha256, sha512, Hash, HashEngine,
+};
+use clap::ArgEnum;
+
+use std::{
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{error::Failure, open_input};
+
+/// Start of the line holding the MAC, which no format's lines start with
+pub const PREFIX: &str = "mac:";
+
+/// Hash function under the HMAC and of `--checksum-share`, picked with `--checksum-algorithm`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum Algorithm {
+    Sha256,
+    Sha512,
+}
+
+impl Algorithm {
+    /// How it's usually written
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "SHA-256",
+            Self::Sha512 => "SHA-512",
+        }
+    }
+    /// The plain hash of `bytes`, without a key
+    pub fn digest(self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha256 => sha256::Hash::hash(bytes).into_inner().to_vec(),
+            Self::Sha512 => sha512::Hash::hash(bytes).into_inner().to_vec(),
+        }
+    }
+}
+
+enum Engine {
+    Sha256(HmacEngine<sha256::Hash>),
+    Sha512(HmacEngine<sha512::Hash>),
+}
+
+impl Engine {
+    fn new(algorithm: Algorithm, key: &[u8]) -> Self {
+        match algorithm {
+            Algorithm::Sha256 => Self::Sha256(HmacEngine::new(key)),
+            Algorithm::Sha512 => Self::Sha512(HmacEngine::new(key)),
+        }
+    }
+    fn input(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha256(engine) => engine.input(bytes),
+            Self::Sha512(engine) => engine.input(bytes),
+        }
+    }
+    fn finish(self) -> Vec<u8> {
+        match self {
+            Self::Sha256(engine) => Hmac::from_engine(engine).into_inner().to_vec(),
+            Self::Sha512(engine) => Hmac::from_engine(engine).into_inner().to_vec(),
+        }
+    }
+}
+
+/// The key in `path`, without a trailing newline
+pub fn read_key(path: &Path) -> Vec<u8> {
+    let mut key = std::fs::read(path)
+        .unwrap_or_else(|err| panic!("couldn't read key {}: {err}", path.display()));
+    while key.last().is_some_and(|&b| b == b'\n' || b == b'\r') {
+        key.pop();
+    }
+    key
+}
+
+/// A writer that also feeds everything written through it into an HMAC, if it has a key
+pub struct MacWriter<W> {
+    inner: W,
+    engine: Option<Engine>,
+}
+
+impl<W: Write> MacWriter<W> {
+    pub fn new(inner: W, key: Option<&[u8]>, algorithm: Algorithm) -> Self {
+        Self {
+            inner,
+            engine: key.map(|key| Engine::new(algorithm, key)),
+        }
+    }
+    /// Append the MAC line, if there's a key
+    pub fn finish(mut self) -> W {
+        if let Some(engine) = self.engine.take() {
+            let mac = engine.finish().to_hex();
+            writeln!(self.inner, "{PREFIX}{mac}")
+                .unwrap_or_else(|err| panic!("couldn't write: {err}"));
+        }
+        self.inner
+    }
+}
+
+impl<W: Write> Write for MacWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if let Some(engine) = &mut self.engine {
+            engine.input(&buf[..n]);
+        }
+        Ok(n)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Check the MAC line at the end of every one of `paths`, except up to `unmacced` that have none
+pub fn verify(paths: &[PathBuf], key: &[u8], algorithm: Algorithm, mut unmacced: usize) {
+    for (f, path) in paths.iter().enumerate() {
+        assert!(
+            path.as_os_str() != "-",
+            "standard input can only be read once, so its MAC can't be checked"
+        );
+        let mut file = open_input(path);
+        let mut engine = Engine::new(algorithm, key);
+        let (mut line, mut last) = (Vec::new(), Vec::new());
+        while file.read_until(b'\n', &mut line).expect("couldn't read") != 0 {
+            engine.input(&last);
+            std::mem::swap(&mut line, &mut last);
+            line.clear();
+        }
+        let Some(hex) = std::str::from_utf8(&last)
+            .ok()
+            .and_then(|l| l.trim().strip_prefix(PREFIX))
+        else {
+            if unmacced == 0 {
+                Failure::new("mac", "has no MAC line").file(f).raise();
+            }
+            unmacced -= 1;
+            continue;
+        };
+        let expected =
+            Vec::<u8>::from_hex(hex).unwrap_or_else(|e| Failure::new("mac", e).file(f).raise());
+        let actual = engine.finish();
+        if expected.len() != actual.len() {
+            let name = algorithm.to_possible_value().expect("not skipped");
+            Failure::new(
+                "mac",
+                format_args!(
+                    "the MAC line isn't {}, try --checksum-algorithm",
+                    name.get_name()
+                ),
+            )
+            .file(f)
+            .raise();
+        }
+        if !crate::ct::eq(&expected, &actual) {
+            Failure::new(
+                "mac",
+                format_args!(
+                    "{} doesn't match its MAC, it was changed or the key is wrong",
+                    path.display()
+                ),
+            )
+            .file(f)
+            .raise();
+        }
+    }
+}
@@ -1,18 +1,125 @@
 #![warn(clippy::pedantic, clippy::nursery)]
-use bip39::{Language::English, Mnemonic};
-use clap::{Args, Parser, Subcommand};
+
+/// Run `$body` with `$n` as a constant of the line width in bytes that `--bits` picked
+///
+/// Defined before the modules so that they can use it too
+macro_rules! with_width {
+    ($style:expr, $n:ident => $body:expr) => {
+        match $style.bits {
+            128 => {
+                const $n: usize = 16;
+                $body
+            }
+            192 => {
+                const $n: usize = 24;
+                $body
+            }
+            256 => {
+                const $n: usize = 32;
+                $body
+            }
+            bits => unreachable!("clap checked --bits {bits}"),
+        }
+    };
+}
+
+mod audit;
+mod buffer;
+mod canonical;
+mod checksum;
+mod chunk;
+mod clipboard;
+mod config;
+mod count;
+mod date;
+mod diff;
+mod error;
+mod explain;
+mod framed;
+mod gzip;
+mod info;
+mod interleave;
+mod interrupt;
+mod log;
+mod mac;
+mod memlock;
+mod meta;
+mod parity;
+mod prefetch;
+mod report;
+mod retry;
+mod schedule;
+mod selftest;
+mod sheet;
+mod shuffle;
+mod sort;
+mod tar;
+mod transform;
+mod verify;
+mod wordlist;
+
+use bitcoin_hashes::{hex::ToHex, sha256, Hash};
+use chunk::Chunked;
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use error::Failure;
+use format::{Encoding, Style};
 use rand::{rngs::OsRng, Fill};
+use retry::Retry;
 use smallvec::{smallvec, SmallVec};
+use xoriaz::{ct, format, XorAccumulator};
 
 use std::{
+    collections::VecDeque,
     fs::{File, OpenOptions},
-    io::{stdout, BufRead, BufReader, BufWriter, Write},
+    io::{stdout, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
     iter::zip,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
 };
 
 #[derive(Debug, Parser)]
+#[allow(clippy::struct_excessive_bools)]
 struct Cli {
+    /// TOML file with defaults for any of the options below, like `lines = 1000`
+    ///
+    /// Options given on the command line take precedence
+    #[clap(long, global = true, parse(from_os_str))]
+    config: Option<PathBuf>,
+    /// Report errors as a single JSON object on stderr, like `{"error":"parse","line":4,...}`
+    #[clap(long, global = true)]
+    json_errors: bool,
+    /// How much to report on stderr about files and line counts, never about their content
+    #[clap(long, global = true, arg_enum, default_value = "off")]
+    log_level: log::Level,
+    /// Append what was done to FILE when done, what was read and whether it worked, for audits
+    #[clap(long, global = true, value_name = "FILE", parse(from_os_str))]
+    report_file: Option<PathBuf>,
+    /// Retry reads and writes failing with transient errors, like timeouts on a network mount,
+    /// up to N times, waiting 0.1s and then twice as long each time
+    ///
+    /// Errors like missing permissions still fail right away
+    #[clap(long, global = true, value_name = "N", default_value_t = 0)]
+    io_retries: u32,
+    /// Size the read buffer of each file after it and the memory available, up to 8 MiB
+    ///
+    /// Instead of 8 KiB for all of them. `--log-level debug` shows what each got
+    #[clap(long, global = true)]
+    auto_buffer: bool,
+    /// Lock all memory into RAM before anything is read, so no secret gets swapped out to disk
+    ///
+    /// Fails if the system doesn't allow it, see `ulimit -l`. Only on Linux
+    #[clap(long, global = true)]
+    mlock: bool,
+    /// Fail at the end of a run that printed any warnings, like about misaligned files
+    ///
+    /// For scripts, where nobody reads the warnings
+    #[clap(long, global = true)]
+    fail_on_warning: bool,
+    /// Only print what the command would read, create and write, and exit without doing any of it
+    ///
+    /// Sources are looked at for their line counts, never decoded
+    #[clap(long, global = true)]
+    explain: bool,
     #[clap(subcommand)]
     command: Command,
 }
@@ -24,201 +131,2170 @@ enum Command {
     Split(Split),
     /// XOR source files together
     ///
-    /// use `-o`/`--out` to specify an output file
+    /// use `-o`/`--out` to specify an output file,
+    /// and `-` as one of the sources to read it from standard input
     Xor(Xor),
     /// Check that a set of file includes the original and all splits
     ///
     /// This means their XOR sum is all zeros
     Check(Check),
+    /// Look for lines that show up more than once in files whose pads should be independent
+    Audit(audit::Audit),
+    /// Summarize a file without showing its lines
+    Info(info::Info),
+    /// Count the lines of files without decoding them, much faster than `info`
+    Count(count::Count),
+    /// Show at which lines two files hold different entropy, whatever their formats
+    Diff(diff::Diff),
+    /// Rewrite a file in another format, like words as hex
+    Convert(transform::Convert),
+    /// Rewrite a hand-edited file exactly as it would have been written, to the space and letter case
+    Canonicalize(canonical::Canonicalize),
+    /// Reorder the lines of a file by a seeded, reversible permutation
+    Shuffle(shuffle::Shuffle),
+    /// Split and recombine random data in memory, to check this build works
+    Selftest,
+    /// List the wordlists this build supports for `--language`
+    Languages,
+    /// Check the wordlists built in against digests of the BIP39 ones
+    VerifyWordlist(wordlist::VerifyWordlist),
+    /// Write back lost shares of a split from the others and its parity files, or check them all
+    Recover(parity::Recover),
+    /// Sort the frames of `split --framed` on standard input back into share files
+    Unframe(framed::Unframe),
 }
 #[derive(Debug, Args)]
 struct Gen {
+    /// How many lines to write, or 0 to keep going until Ctrl-C
+    ///
+    /// Ctrl-C then stops after a whole line and keeps what was written, into a single DEST at most
     #[clap(short, long, default_value = "200")]
     lines: usize,
+    /// Generate enough lines to hold this much entropy, like `64K` or `1M`, instead of `--lines`
+    #[clap(long, conflicts_with = "lines", parse(try_from_str = parse_entropy_size))]
+    bytes: Option<u64>,
     #[clap(parse(from_os_str))]
     dest: Vec<PathBuf>,
+    /// Instead of listing `DEST`s, create `<PREFIX>_YYYYMMDD_HHMMSS_<N>.txt` files
+    #[clap(
+        long,
+        value_name = "PREFIX",
+        conflicts_with = "dest",
+        parse(from_os_str)
+    )]
+    timestamped_names: Option<PathBuf>,
+    /// How many files `--timestamped-names` creates
+    #[clap(short = 'n', long, default_value = "1")]
+    count: usize,
+    /// Only print how much would be written, without creating anything
+    #[clap(long)]
+    count_only: bool,
+    /// Testing aid for benchmarks: write this same line every time instead of random ones
+    ///
+    /// The output is NOT random and must never be used as a secret
+    #[clap(long, hide = true, value_name = "LINE")]
+    repeat: Option<String>,
+    /// Roll each `DEST` over into `DEST.001`, `DEST.002`, ... every N lines
+    ///
+    /// `split` and `xor` read such a set back in order when given the bare `DEST`
+    #[clap(long, value_name = "N", conflicts_with = "rotate-size")]
+    rotate_lines: Option<usize>,
+    /// Like `--rotate-lines`, with as many lines as fit in about this size, like `1G`
+    #[clap(long, value_name = "SIZE", parse(try_from_str = parse_size))]
+    rotate_size: Option<u64>,
+    /// Read each file back once it's written, checking every line decodes and the count is right
+    #[clap(long)]
+    verify_written: bool,
+    /// Also describe each written file in a `<DEST>.meta` next to it, for `xor` and `check`
+    #[clap(long)]
+    meta: bool,
+    /// Instead of random lines, write shares of this line that XOR back to it, one per `DEST`
+    ///
+    /// The line shows up in process listings, `--of-file` doesn't have that problem
+    #[clap(
+        long,
+        value_name = "LINE",
+        conflicts_with_all = &["of-file", "lines", "bytes", "repeat", "count-only", "rotate-lines", "rotate-size"]
+    )]
+    of: Option<String>,
+    /// Like `--of`, with all the lines of a file, or of standard input for `-`
+    #[clap(
+        long,
+        value_name = "SECRET",
+        parse(from_os_str),
+        conflicts_with_all = &["lines", "bytes", "repeat", "count-only", "rotate-lines", "rotate-size"]
+    )]
+    of_file: Option<PathBuf>,
+    #[clap(flatten)]
+    style: Style,
+    #[clap(flatten)]
+    output: Output,
 }
 #[derive(Debug, Args)]
 struct Split {
+    /// The secret, or `-` to read it from standard input, like from a pipe, without a file
+    ///
+    /// A last line cut off by the pipe closing early fails to decode like any other bad line
     #[clap(parse(from_os_str))]
     source: PathBuf,
-    #[clap(required = true, min_values = 2, parse(from_os_str))]
+    #[clap(required_unless_present = "shares", min_values = 2, parse(from_os_str))]
     dest: Vec<PathBuf>,
+    /// Instead of listing `DEST`s, create this many named after `--name-template`
+    #[clap(long, value_name = "N", conflicts_with = "dest")]
+    shares: Option<usize>,
+    /// Names of the `--shares`, with `{n}` replaced by the share's number and `{total}` by N
+    #[clap(long, default_value = "share_{n}.txt")]
+    name_template: String,
+    /// Directory the `--shares` are created in
+    #[clap(long, default_value = ".", parse(from_os_str))]
+    output_dir: PathBuf,
+    /// Write all the `--shares` to stdout as binary frames tagged with their index, for `unframe`
+    #[clap(
+        long,
+        conflicts_with_all = &["dest", "mac", "meta", "sheet", "parity", "verify-written"]
+    )]
+    framed: bool,
+    /// Write all the `--shares` to PATH as one stream of plain lines, a line of each in turn, `-` for stdout
+    ///
+    /// Line k of it, counting from 0, is line k / N of share k % N. `xor --deinterleave N` reads it
+    #[clap(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = &["dest", "mac", "meta", "sheet", "parity", "framed", "verify-written"],
+        parse(from_os_str)
+    )]
+    interleave: Option<PathBuf>,
+    /// Also print each share as numbered words into a PDF to hand over, `out.pdf` becomes `out_1.pdf`, ...
+    ///
+    /// Each page says which share of how many it is and the share's file name
+    #[clap(long, value_name = "PDF", parse(from_os_str))]
+    sheet: Option<PathBuf>,
+    /// Read the pads from PATH instead of the system's random number generator
+    ///
+    /// Line by line, a line's width for each share after the first, in order.
+    /// `fdN` reads the inherited file descriptor N, and running out is an error
+    #[clap(long, value_name = "PATH", parse(from_os_str))]
+    pad_source: Option<PathBuf>,
+    /// Only mask bytes A up to B of each line, counting from 0, like `16:32` for the second half
+    ///
+    /// The rest goes into the first share as it is, and the others get zeros there.
+    /// `xor` needs the same range to put the secret back together
+    #[clap(long, value_name = "A:B")]
+    byte_range: Option<ByteRange>,
+    /// Also write a hash of each line of the secret to FILE, for `xor --checksum-share`
+    ///
+    /// It can't help getting the secret back, but it does confirm a correct guess of a whole line
+    #[clap(long, value_name = "FILE", parse(from_os_str))]
+    checksum_share: Option<PathBuf>,
+    /// Also write a parity file to each FILE, so `recover` can write back as many lost shares
+    ///
+    /// Give it once per parity file, they're told apart by their order.
+    /// Fewer files than there are shares still say nothing about the secret
+    #[clap(long, value_name = "FILE", parse(from_os_str))]
+    parity: Vec<PathBuf>,
+    /// Refuse to split into fewer shares than this
+    #[clap(long, default_value = "2")]
+    min_shares: usize,
+    /// Report source lines with obvious structure, like repeated bytes, before splitting
+    ///
+    /// For sources that came from somewhere else and might not be random at all.
+    /// `--audit-source=strict` refuses to split them
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "MODE",
+        min_values = 0,
+        require_equals = true,
+        default_missing_value = "warn"
+    )]
+    audit_source: Option<audit::SourceAudit>,
+    /// End each share with a `mac:` line, an HMAC-SHA256 of it keyed by the contents of `KEY_FILE`
+    #[clap(long, value_name = "KEY_FILE", parse(from_os_str))]
+    mac: Option<PathBuf>,
+    /// Hash function of the `--mac` HMAC and of `--checksum-share`
+    #[clap(long, arg_enum, default_value = "sha256")]
+    checksum_algorithm: mac::Algorithm,
+    /// Print the hex of the source, the pads and the first share of the first N lines to stderr
+    #[clap(long, hide = true, value_name = "N", default_value_t)]
+    dump_entropy: usize,
+    /// Read the shares back once they're written, checking every line decodes, the counts,
+    /// and that they XOR back to the secret
+    #[clap(long)]
+    verify_written: bool,
+    /// Also describe each written file in a `<DEST>.meta` next to it, for `xor` and `check`
+    #[clap(long)]
+    meta: bool,
+    /// With `--meta`, record that the shares aren't to be used before DATE, like `2040-01-01`
+    ///
+    /// Give it once for all shares, or once per share in their order. It's only policy, so nothing
+    /// keeps anyone from combining the shares earlier, except `xor --respect-time-lock`
+    #[clap(long, value_name = "DATE", requires = "meta", parse(try_from_str = date::parse))]
+    not_before: Vec<date::Date>,
+    #[clap(flatten)]
+    range: Range,
+    #[clap(flatten)]
+    style: Style,
+    #[clap(flatten)]
+    output: Output,
 }
 #[derive(Debug, Args)]
+#[allow(clippy::struct_excessive_bools)]
 struct Xor {
-    #[clap(required = true, min_values = 2, parse(from_os_str))]
+    #[clap(required_unless_present_any = &["files-from", "from-tar"], parse(from_os_str))]
     source: Vec<PathBuf>,
+    /// Also read sources from FILE, one path per line, after the ones given directly
+    #[clap(long, value_name = "FILE", parse(from_os_str))]
+    files_from: Option<PathBuf>,
+    /// Sort all the sources first, instead of taking them in the order given
+    ///
+    /// The result is the same either way, but file numbers in errors and logs follow it
+    #[clap(long, arg_enum, value_name = "ORDER")]
+    sort: Option<sort::SortOrder>,
+    /// File to write the result to, stdout when left out or `-`
     #[clap(short = 'o', long = "out", parse(from_os_str))]
     dest: Option<PathBuf>,
+    #[clap(flatten)]
+    range: Range,
+    #[clap(flatten)]
+    style: Style,
+    #[clap(flatten)]
+    output: Output,
+    /// Print the line count and digests of the first and last lines of each source, then exit
+    ///
+    /// Only a hash of each line is shown, never the secret itself
+    #[clap(long)]
+    preview: bool,
+    /// Fold sources in one at a time through temporary files, instead of opening all at once
+    ///
+    /// This needs a pass per source, but only ever holds three files open
+    #[clap(long)]
+    fold: bool,
+    /// Warn about sources that look shifted by a few lines, like by a stray blank line at the top
+    #[clap(long)]
+    detect_misalignment: bool,
+    /// Allow lines of different widths, zero-extending each to the widest one on its line
+    ///
+    /// A 12 word pad over a 24 word secret then only masks the first half of it,
+    /// and the rest comes out as is
+    #[clap(long)]
+    promote: bool,
+    /// Give each output line only the sources of one group, taking the groups in turn
+    ///
+    /// Groups of 0-based source positions like `0,1;1,2;0,2`. To get a source back,
+    /// run the same schedule with the output in its place, and `--recover` its position
+    #[clap(long, value_name = "GROUPS", conflicts_with = "fold")]
+    schedule: Option<schedule::Schedule>,
+    /// With `--schedule`, the 0-based position of an output standing in for a lost source
+    ///
+    /// Only the lines whose group has it come out as the lost source's lines,
+    /// this warns about the rest, which come out as they were in the output
+    #[clap(long, value_name = "POSITION", requires = "schedule")]
+    recover: Option<usize>,
+    /// Only XOR bytes A up to B of each line, counting from 0, and copy the rest from the first source
+    ///
+    /// Gives back the secret of a `split --byte-range` with the same range
+    #[clap(long, value_name = "A:B")]
+    byte_range: Option<ByteRange>,
+    /// XOR the entropy of this mnemonic into every line too, like a share memorized instead of written down
+    ///
+    /// The same on every line. Anything on the command line can be seen by other users
+    /// in the list of processes and stays in the shell's history
+    #[clap(long, value_name = "WORDS", conflicts_with = "fold")]
+    with_mnemonic: Option<String>,
+    /// Check every line of the result against a `split --checksum-share` FILE
+    #[clap(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = &["fold", "schedule"],
+        parse(from_os_str)
+    )]
+    checksum_share: Option<PathBuf>,
+    /// Read each source ahead in a thread of its own, for slow or remote storage
+    #[clap(long)]
+    parse_threads: bool,
+    /// Take N shares apart from the single source, a stream from `split --interleave`
+    #[clap(
+        long,
+        value_name = "N",
+        conflicts_with_all = &["fold", "preview", "parse-threads", "detect-misalignment"]
+    )]
+    deinterleave: Option<usize>,
+    /// Take the shares from the regular files in a tar ARCHIVE, by the order of their names
+    ///
+    /// Nothing gets unpacked to disk, and `.meta` files in it are left out
+    #[clap(
+        long,
+        value_name = "ARCHIVE",
+        conflicts_with_all = &[
+            "source", "files-from", "sort", "fold", "preview", "parse-threads",
+            "detect-misalignment", "deinterleave", "mac"
+        ],
+        parse(from_os_str)
+    )]
+    from_tar: Option<PathBuf>,
+    /// Accept a single source, which just gets copied since there's nothing to XOR it with
+    ///
+    /// Almost always one was left out by mistake, like by a glob or `--files-from` matching less than meant
+    #[clap(long, conflicts_with = "fold")]
+    allow_single_input: bool,
+    /// Refuse sources whose `.meta` has a `not-before` date that hasn't come yet, instead of warning
+    #[clap(long)]
+    respect_time_lock: bool,
+    /// Copy the result to the clipboard instead of writing it out, if it's a single line
+    #[clap(long, conflicts_with_all = &["dest", "fold"])]
+    clipboard: bool,
+    /// Clear the clipboard again after this many seconds
+    #[clap(long, value_name = "SECONDS", requires = "clipboard")]
+    clear_after: Option<u64>,
+    /// Refuse sources whose `mac:` line doesn't match, with the key in `KEY_FILE`
+    #[clap(long, value_name = "KEY_FILE", parse(from_os_str))]
+    mac: Option<PathBuf>,
+    /// Hash function of the `--mac` HMAC and of `--checksum-share`
+    ///
+    /// A `.meta` that records one stands in for the default
+    #[clap(long, arg_enum, default_value = "sha256")]
+    checksum_algorithm: mac::Algorithm,
+    /// Print the hex of every source and the running XOR of the first N lines to stderr
+    #[clap(long, hide = true, value_name = "N", default_value_t)]
+    dump_entropy: usize,
+}
+/// Only process a slice of the input lines
+#[derive(Debug, Args, Clone, Copy, Default)]
+struct Range {
+    /// First line to process, counting from 1
+    #[clap(long, default_value = "1")]
+    from_line: usize,
+    /// Last line to process, inclusive
+    #[clap(long)]
+    to_line: Option<usize>,
+    /// Fail unless exactly this many lines get processed, to catch truncated sources
+    #[clap(long, value_name = "N")]
+    expect_lines: Option<usize>,
+    /// Ignore this many lines at the top of every source, like another tool's header
+    ///
+    /// Line 1 is the first one after them
+    #[clap(long, value_name = "N", default_value_t)]
+    skip_header_lines: usize,
+}
+impl Range {
+    /// Whether no lines are left out
+    const fn is_everything(self) -> bool {
+        self.from_line <= 1 && self.to_line.is_none()
+    }
+    /// Number of lines to skip before processing starts
+    const fn skip(self) -> usize {
+        self.from_line.saturating_sub(1)
+    }
+    /// Number of lines of each source to read past before processing starts, header included
+    const fn read_past(self) -> usize {
+        self.skip_header_lines + self.skip()
+    }
+    /// Whether the 0-based line `i` is past the end of the range
+    fn done(self, i: usize) -> bool {
+        self.to_line.is_some_and(|to| i >= to)
+    }
+    /// Check `--expect-lines` once processing stopped before the 0-based line `i`
+    fn finish(self, i: usize) {
+        let got = i - self.skip();
+        if let Some(expected) = self.expect_lines.filter(|&n| n != got) {
+            Failure::new(
+                "length",
+                format_args!("expected {expected} lines, got {got}"),
+            )
+            .raise();
+        }
+    }
 }
 #[derive(Debug, Args)]
 struct Check {
     #[clap(required = true, min_values = 3, parse(from_os_str))]
     source: Vec<PathBuf>,
+    /// Refuse shares whose `mac:` line doesn't match, with the key in `KEY_FILE`
+    ///
+    /// Only the original, which was never split, may have no `mac:` line
+    #[clap(long, value_name = "KEY_FILE", parse(from_os_str))]
+    mac: Option<PathBuf>,
+    /// Hash function of the `--mac` HMAC
+    ///
+    /// A `.meta` that records one stands in for the default
+    #[clap(long, arg_enum, default_value = "sha256")]
+    checksum_algorithm: mac::Algorithm,
+    /// Only compare every Kth line, starting with the first, for a quick look at a huge set
+    ///
+    /// The other lines are still read to find the next one, but not decoded
+    #[clap(long, value_name = "K")]
+    every_nth: Option<NonZeroUsize>,
+    /// Refuse files whose `.meta` has a `not-before` date that hasn't come yet, instead of warning
+    #[clap(long)]
+    respect_time_lock: bool,
+    #[clap(flatten)]
+    style: Style,
 }
 
 fn main() {
-    match Cli::parse().command {
+    // before the config is read, so its errors come out like any other
+    error::install_hook(config::flag_from_args("--json-errors"));
+    let mut cmd = Cli::command();
+    if let Some(path) = config::path_from_args() {
+        cmd = config::apply(cmd, &path);
+    }
+    let matches = cmd.get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    error::install_hook(cli.json_errors);
+    log::set(cli.log_level);
+    retry::set(cli.io_retries);
+    buffer::set(cli.auto_buffer);
+    log::fail_on_warning(cli.fail_on_warning);
+    if cli.explain {
+        return explain::run(&cli.command, &matches);
+    }
+    if cli.mlock {
+        memlock::lock_all();
+    }
+    if let Some(path) = cli.report_file {
+        report::start(path, matches.subcommand_name().unwrap_or_default());
+    }
+    match cli.command {
         Command::Gen(args) => gen(args),
         Command::Split(args) => split(args),
         Command::Xor(args) => xor(args),
         Command::Check(args) => check(args),
+        Command::Info(args) => info::run(args),
+        Command::Count(args) => count::run(args),
+        Command::Diff(args) => diff::run(args),
+        Command::Convert(args) => transform::run(args),
+        Command::Canonicalize(args) => canonical::run(args),
+        Command::Audit(args) => audit::run(args),
+        Command::Shuffle(args) => shuffle::run(args),
+        Command::Selftest => selftest::run(),
+        Command::Unframe(args) => framed::run(args),
+        Command::Recover(args) => parity::run(args),
+        Command::VerifyWordlist(args) => wordlist::run(args),
+        Command::Languages => {
+            for &language in bip39::Language::all() {
+                println!("{}", format::language_name(language));
+            }
+        }
     }
+    log::finish();
+    report::finish(None);
 }
 
-fn gen(Gen { lines, dest }: Gen) {
-    let new_files = create_files(&dest);
+fn gen(
+    Gen {
+        mut lines,
+        bytes,
+        mut dest,
+        timestamped_names,
+        count,
+        count_only,
+        repeat,
+        rotate_lines,
+        rotate_size,
+        verify_written,
+        meta,
+        of,
+        of_file,
+        style,
+        output,
+    }: Gen,
+) {
+    report::style(&style);
+    if let Some(prefix) = timestamped_names {
+        dest = timestamped(&prefix, count);
+    }
+    let secret: Option<Input> = match (of, of_file) {
+        (Some(line), _) => Some(Box::new(std::io::Cursor::new(line + "\n"))),
+        (None, Some(path)) => Some(open_input(&path)),
+        (None, None) => None,
+    };
+    if let Some(mut secret) = secret {
+        return gen_shares_of(&mut secret, &dest, &style, output, meta, verify_written);
+    }
+    if let Some(bytes) = bytes {
+        let width = style.bytes() as u64;
+        lines = usize::try_from(bytes.div_ceil(width)).expect("too many lines");
+        if bytes % width != 0 {
+            log::warn(format_args!(
+                "{bytes} bytes isn't a whole number of {width} byte lines, rounding up to {lines} lines"
+            ));
+        }
+    }
+    if count_only {
+        return print_gen_size(dest.len(), lines, &style);
+    }
+    if lines == 0 {
+        assert!(
+            dest.len() <= 1 && rotate_lines.is_none() && rotate_size.is_none(),
+            "--lines 0 only stops at Ctrl-C, so it can only write one file without rotating"
+        );
+        interrupt::catch();
+        eprintln!("writing lines until Ctrl-C");
+    }
+    let repeat = repeat.map(|line| {
+        let entropy = Style {
+            index: false,
+            ..style.clone()
+        }
+        .decode(&line, 1)
+        .unwrap_or_else(|e| Failure::new("parse", e).raise());
+        check_width(&entropy, style.bytes(), None, 0);
+        entropy
+    });
+    let mut file_lines: SmallVec<[usize; 4]> = smallvec![lines; dest.len()];
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #[allow(clippy::cast_precision_loss)]
+    let rotate = rotate_lines
+        .or_else(|| rotate_size.map(|size| (size as f64 / style.line_len(style.bytes())) as usize));
+    if let Some(per_file) = rotate {
+        assert!(per_file > 0, "can't rotate files before their first line");
+        assert!(!dest.is_empty(), "only files can be rotated, not stdout");
+        let parts = lines.div_ceil(per_file).max(1);
+        (dest, file_lines) = dest
+            .iter()
+            .flat_map(|path| {
+                (1..=parts).map(move |n| {
+                    (
+                        rotated_name(path, n),
+                        per_file.min(lines - (n - 1) * per_file),
+                    )
+                })
+            })
+            .unzip();
+    }
+    let new_files = create_files(&dest, output);
     if new_files.is_empty() {
-        gen_inner(stdout().lock(), lines);
+        let mut w = Chunked::new(stdout().lock(), output.chunk_lines);
+        with_width!(style, N => gen_inner::<N>(
+            &mut w,
+            lines,
+            &style,
+            repeat.as_deref(),
+            output.checkpoint_every,
+        ));
+        w.finish();
     }
-    for file in new_files {
-        gen_inner(BufWriter::new(file), lines);
+    for (f, ((file, lines), path)) in zip(zip(new_files, file_lines), &dest).enumerate() {
+        preallocate(&file, path, expected_size(lines, &style), output);
+        let mut w = BufWriter::new(file);
+        let mut chunked = Chunked::new(&mut w, output.chunk_lines);
+        let lines = with_width!(style, N => gen_inner::<N>(
+            &mut chunked,
+            lines,
+            &style,
+            repeat.as_deref(),
+            output.checkpoint_every,
+        ));
+        chunked.finish();
+        finish_file(w);
+        if meta {
+            meta::write(path, &style, lines, None, None, output);
+        }
+        if verify_written {
+            verify::lines(path, f, &style, lines);
+        }
     }
 }
 
-fn create_file(path: &Path) -> BufWriter<File> {
-    BufWriter::new(
-        OpenOptions::new()
-            .create_new(true)
+/// `count` paths of `--timestamped-names` starting with `prefix`
+fn timestamped(prefix: &Path, count: usize) -> Vec<PathBuf> {
+    let stamp = utc_timestamp();
+    (1..=count)
+        .map(|n| {
+            let mut name = prefix.as_os_str().to_owned();
+            name.push(format!("_{stamp}_{n}.txt"));
+            name.into()
+        })
+        .collect()
+}
+
+/// Split `secret` straight into new shares at `dest`, like `split` does
+fn gen_shares_of(
+    secret: &mut impl BufRead,
+    dest: &[PathBuf],
+    style: &Style,
+    output: Output,
+    meta: bool,
+    verify_written: bool,
+) {
+    assert!(
+        dest.len() >= 2,
+        "--of needs at least 2 DESTs to split into, {} given",
+        dest.len()
+    );
+    let mut files: SmallVec<[_; 4]> = create_files(dest, output)
+        .into_iter()
+        .map(BufWriter::new)
+        .collect();
+    let lines = with_width!(style, N => split_inner::<N>(
+        secret,
+        &mut files,
+        Range::default(),
+        style,
+        None,
+        None,
+        None,
+        output.checkpoint_every,
+    ));
+    for file in files {
+        finish_file(file);
+    }
+    for (f, path) in dest.iter().enumerate() {
+        if meta {
+            meta::write(path, style, lines, None, None, output);
+        }
+        if verify_written {
+            verify::lines(path, f, style, lines);
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn print_gen_size(files: usize, lines: usize, style: &Style) {
+    let line_len = style.line_len(style.bytes());
+    #[allow(clippy::cast_precision_loss)]
+    let file_len = (line_len * lines as f64).round() as u64;
+    if files == 0 {
+        println!("stdout: {lines} lines");
+    } else {
+        println!("{files} files of {lines} lines each");
+    }
+    println!("about {line_len:.1} bytes per line, {file_len} bytes per file");
+    println!("{} bytes in total", file_len * files.max(1) as u64);
+}
+
+/// `path.NNN`, the `n`th part of a rotated set
+fn rotated_name(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n:03}"));
+    name.into()
+}
+
+/// All of `path.001`, `path.002`, ... read one after the other, if `path` itself doesn't exist
+fn open_rotated(path: &Path) -> Option<Input> {
+    if os_path(path).exists() || !os_path(&rotated_name(path, 1)).exists() {
+        return None;
+    }
+    let mut set: Box<dyn Read> = Box::new(std::io::empty());
+    for n in 1.. {
+        let part = rotated_name(path, n);
+        if !os_path(&part).exists() {
+            break;
+        }
+        set = Box::new(set.chain(open_file(&part)));
+    }
+    Some(Box::new(BufReader::new(set)))
+}
+
+/// `--byte-range A:B`, the bytes of each line from 0-based A up to but not including B
+#[derive(Debug, Clone, Copy)]
+struct ByteRange {
+    start: usize,
+    end: usize,
+}
+impl std::str::FromStr for ByteRange {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (start, end) = s.split_once(':').ok_or("expected A:B")?;
+        let number = |n: &str| n.parse().map_err(|e| format!("`{n}`: {e}"));
+        let (start, end) = (number(start)?, number(end)?);
+        if start >= end {
+            return Err(format!("{start}:{end} is empty"));
+        }
+        Ok(Self { start, end })
+    }
+}
+impl ByteRange {
+    /// Panic unless the range fits in lines of `width` bytes
+    fn check(self, width: usize) {
+        assert!(
+            self.end <= width,
+            "--byte-range {}:{} goes past the {width} bytes of a line",
+            self.start,
+            self.end
+        );
+    }
+    /// Overwrite the bytes of `line` outside the range with those of `with`
+    fn copy_outside(self, line: &mut [u8], with: &[u8]) {
+        line[..self.start].copy_from_slice(&with[..self.start]);
+        line[self.end..].copy_from_slice(&with[self.end..]);
+    }
+}
+
+/// Parse a byte count with an optional binary `K`, `M`, `G` or `T` suffix
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim_end_matches(['B', 'b']).trim_end_matches('i');
+    let (digits, shift) = match s.as_bytes().last() {
+        Some(b'k' | b'K') => (&s[..s.len() - 1], 10),
+        Some(b'm' | b'M') => (&s[..s.len() - 1], 20),
+        Some(b'g' | b'G') => (&s[..s.len() - 1], 30),
+        Some(b't' | b'T') => (&s[..s.len() - 1], 40),
+        _ => (s, 0),
+    };
+    let n: u64 = digits.trim().parse().map_err(|e| format!("{e}"))?;
+    n.checked_mul(1 << shift)
+        .ok_or_else(|| "size too large".to_owned())
+}
+
+/// A [`parse_size`] for `gen --bytes`, where 0 would otherwise mean `--lines 0`, forever
+fn parse_entropy_size(s: &str) -> Result<u64, String> {
+    match parse_size(s)? {
+        0 => Err("there has to be at least one byte, use --lines 0 to keep going".to_owned()),
+        n => Ok(n),
+    }
+}
+
+/// Current UTC time as `YYYYMMDD_HHMMSS`
+fn utc_timestamp() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("clock is before 1970")
+        .as_secs();
+    let (year, month, day) = date::civil(secs / 86400);
+    let secs = secs % 86400;
+    format!(
+        "{year:04}{month:02}{day:02}_{:02}{:02}{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Windows only accepts paths longer than `MAX_PATH` in their verbatim `\\?\` form
+#[cfg(windows)]
+fn os_path(path: &Path) -> std::borrow::Cow<Path> {
+    use std::{borrow::Cow, ffi::OsString, path::Component, path::Prefix};
+    const MAX_PATH: usize = 260;
+    if path.as_os_str().len() < MAX_PATH {
+        return Cow::Borrowed(path);
+    }
+    let abs = std::path::absolute(path)
+        .unwrap_or_else(|err| panic!("couldn't resolve long path {}: {err}", path.display()));
+    let mut components = abs.components();
+    let verbatim = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(disk) => PathBuf::from(format!(r"\\?\{}:\", char::from(disk))),
+            Prefix::UNC(server, share) => {
+                let mut p = OsString::from(r"\\?\UNC\");
+                p.push(server);
+                p.push(r"\");
+                p.push(share);
+                p.push(r"\");
+                PathBuf::from(p)
+            }
+            Prefix::Verbatim(_) | Prefix::VerbatimUNC(..) | Prefix::VerbatimDisk(_) => {
+                return Cow::Owned(abs)
+            }
+            Prefix::DeviceNS(_) => panic!(
+                "{} is {} characters long, which device paths don't support",
+                path.display(),
+                path.as_os_str().len()
+            ),
+        },
+        _ => return Cow::Owned(abs),
+    };
+    Cow::Owned(
+        components
+            .filter(|c| !matches!(c, Component::RootDir))
+            .fold(verbatim, |mut p, c| {
+                p.push(c);
+                p
+            }),
+    )
+}
+#[cfg(not(windows))]
+const fn os_path(path: &Path) -> &Path {
+    path
+}
+
+/// How output files get created
+#[derive(Debug, Args, Clone, Copy)]
+#[allow(clippy::struct_field_names)]
+struct Output {
+    /// Accept existing outputs if they already hold exactly what would be written
+    ///
+    /// Otherwise nothing is ever overwritten
+    #[clap(long)]
+    overwrite_if_identical: bool,
+    /// Flush outputs to disk every N lines, so a crash loses at most N lines
+    ///
+    /// 0 leaves it to the usual buffering
+    #[clap(long, value_name = "N", default_value_t)]
+    checkpoint_every: usize,
+    /// Write lines out N at a time, in one write each, instead of line by line
+    ///
+    /// 0 leaves it to the usual buffering, which for stdout is a write per line
+    #[clap(long, value_name = "N", default_value_t)]
+    chunk_lines: usize,
+    /// Unix permissions of created files, in octal
+    ///
+    /// The umask can only take more away. Ignored on Windows
+    #[clap(long, value_name = "MODE", default_value = "600", parse(try_from_str = parse_mode))]
+    output_permissions: u32,
+    /// Reserve disk space for the expected size of each output before writing it, for less fragmentation
+    ///
+    /// The size itself still only grows as lines are written. Only does anything on Linux
+    #[clap(long)]
+    preallocate: bool,
+}
+impl Default for Output {
+    fn default() -> Self {
+        Self {
+            overwrite_if_identical: false,
+            checkpoint_every: 0,
+            chunk_lines: 0,
+            output_permissions: 0o600,
+            preallocate: false,
+        }
+    }
+}
+
+fn parse_mode(s: &str) -> Result<u32, String> {
+    match u32::from_str_radix(s, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        Ok(_) => Err("that's more than permission bits".to_owned()),
+        Err(e) => Err(format!("{e}")),
+    }
+}
+
+/// A freshly created output file, or an existing one that all output is compared against
+enum Dest {
+    New(Retry<File>),
+    Same(BufReader<Retry<File>>, PathBuf),
+}
+impl Dest {
+    /// Flush, and make sure an existing file doesn't continue past the output
+    fn finish(self) -> std::io::Result<()> {
+        match self {
+            Self::New(mut file) => file.flush(),
+            Self::Same(mut existing, path) => {
+                if existing.fill_buf()?.is_empty() {
+                    Ok(())
+                } else {
+                    Err(differs(&path))
+                }
+            }
+        }
+    }
+}
+fn differs(path: &Path) -> std::io::Error {
+    std::io::Error::other(format!("{} exists with different content", path.display()))
+}
+impl Write for Dest {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::New(file) => file.write(buf),
+            Self::Same(existing, path) => {
+                if buf.is_empty() {
+                    return Ok(0);
+                }
+                let have = existing.fill_buf()?;
+                let n = have.len().min(buf.len());
+                if n == 0 || have[..n] != buf[..n] {
+                    return Err(differs(path));
+                }
+                existing.consume(n);
+                Ok(n)
+            }
+        }
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::New(file) => file.flush(),
+            Self::Same(..) => Ok(()),
+        }
+    }
+}
+
+/// Whether `path` is the platform's null device, which is always fine to write to
+fn is_null_device(path: &Path) -> bool {
+    if cfg!(windows) {
+        path.as_os_str().eq_ignore_ascii_case("NUL")
+    } else {
+        path == Path::new("/dev/null")
+    }
+}
+
+/// Create `path`, which mustn't exist yet, except for an identical file with
+/// `--overwrite-if-identical` and the null device, so a run can be timed without keeping anything
+fn create_dest(opts: &OpenOptions, path: &Path, output: Output) -> std::io::Result<Dest> {
+    if is_null_device(path) {
+        return OpenOptions::new()
             .write(true)
             .open(path)
-            .expect("btw, I won't overwrite"),
-    )
+            .map(|file| Dest::New(Retry(file)));
+    }
+    match opts.open(os_path(path)) {
+        Ok(file) => {
+            log::info(format_args!("created {}", path.display()));
+            Ok(Dest::New(Retry(file)))
+        }
+        Err(err)
+            if err.kind() == std::io::ErrorKind::AlreadyExists && output.overwrite_if_identical =>
+        {
+            let existing = File::open(os_path(path))?;
+            log::info(format_args!(
+                "comparing against the existing {}",
+                path.display()
+            ));
+            Ok(Dest::Same(BufReader::new(Retry(existing)), path.to_owned()))
+        }
+        Err(err) => Err(err),
+    }
 }
 
-fn create_files(paths: &[PathBuf]) -> SmallVec<[File; 4]> {
-    let mut files = SmallVec::with_capacity(paths.len());
+/// Reserve about `bytes` of disk space for a new output at `path` with `--preallocate`,
+/// without changing its length. Failing that is only worth a warning
+fn preallocate(dest: &Dest, path: &Path, bytes: u64, output: Output) {
+    if !output.preallocate || bytes == 0 || is_null_device(path) {
+        return;
+    }
+    #[cfg(target_os = "linux")]
+    if let Dest::New(Retry(file)) = dest {
+        use std::os::unix::io::AsRawFd;
+        let len = libc::off_t::try_from(bytes).unwrap_or(libc::off_t::MAX);
+        // SAFETY: the descriptor stays open for the call, and fallocate touches no memory
+        let ret = unsafe { libc::fallocate(file.as_raw_fd(), libc::FALLOC_FL_KEEP_SIZE, 0, len) };
+        if ret == 0 {
+            log::debug(format_args!(
+                "reserved {bytes} bytes for {}",
+                path.display()
+            ));
+        } else {
+            let err = std::io::Error::last_os_error();
+            log::warn(format_args!(
+                "couldn't preallocate {}: {err}",
+                path.display()
+            ));
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = dest;
+}
+
+/// Expected size of `lines` lines written in `style`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+#[allow(clippy::cast_precision_loss)]
+fn expected_size(lines: usize, style: &Style) -> u64 {
+    (lines as f64 * style.line_len(style.bytes())).ceil() as u64
+}
+
+/// Size of the file at `path`, if it is one, for outputs about as big as their source
+fn source_size(path: &Path) -> u64 {
+    if path.as_os_str() == "-" {
+        return 0;
+    }
+    std::fs::metadata(os_path(path)).map_or(0, |meta| meta.len())
+}
+
+/// Refuse `path` early if it's a directory, instead of failing confusingly on I/O later
+fn expect_not_dir(path: &Path) {
+    let is_dir = std::fs::metadata(os_path(path)).is_ok_and(|meta| meta.is_dir());
+    assert!(
+        !is_dir,
+        "expected a file but {} is a directory",
+        path.display()
+    );
+}
+
+/// Options that create a new file, with the permissions from `output` where there are any
+fn new_file_options(output: Output) -> OpenOptions {
     let mut opts = OpenOptions::new();
     opts.create_new(true).write(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut opts, output.output_permissions);
+    #[cfg(not(unix))]
+    let _ = output;
+    opts
+}
+
+fn create_file(path: &Path, output: Output) -> BufWriter<Dest> {
+    expect_not_dir(path);
+    let opts = new_file_options(output);
+    BufWriter::new(
+        create_dest(&opts, path, output)
+            .unwrap_or_else(|err| panic!("btw, I won't overwrite {}: {err}", path.display())),
+    )
+}
+
+/// Flush a file from [`create_file`], checking an identical existing one to the end
+fn finish_file(w: BufWriter<Dest>) {
+    w.into_inner()
+        .map_err(std::io::IntoInnerError::into_error)
+        .and_then(Dest::finish)
+        .unwrap_or_else(|err| panic!("couldn't write: {err}"));
+}
+
+fn create_files(paths: &[PathBuf], output: Output) -> SmallVec<[Dest; 4]> {
+    for path in paths {
+        expect_not_dir(path);
+    }
+    let mut files = SmallVec::with_capacity(paths.len());
+    let opts = new_file_options(output);
     for path in paths {
-        match opts.open(path) {
+        match create_dest(&opts, path, output) {
             Ok(file) => files.push(file),
             Err(err) => {
                 for (file, path) in zip(files, paths) {
-                    drop(file);
-                    if let Err(err) = std::fs::remove_file(path) {
-                        eprintln!("{err}");
-                    };
+                    if let Dest::New(file) = file {
+                        drop(file);
+                        if is_null_device(path) {
+                            continue;
+                        }
+                        match std::fs::remove_file(path) {
+                            Ok(()) => log::info(format_args!("removed {} again", path.display())),
+                            Err(err) => eprintln!("{err}"),
+                        }
+                    }
                 }
-                panic!("btw, I won't overwrite: {err}");
+                panic!("btw, I won't overwrite {}: {err}", path.display());
             }
         }
     }
     files
 }
 
-fn open_file(path: &Path) -> BufReader<File> {
-    BufReader::new(
-        OpenOptions::new()
-            .read(true)
-            .open(path)
-            .expect("couldn't open for reading"),
-    )
+fn open_file(path: &Path) -> BufReader<Retry<File>> {
+    expect_not_dir(path);
+    let file = OpenOptions::new()
+        .read(true)
+        .open(os_path(path))
+        .unwrap_or_else(|err| panic!("couldn't open {} for reading: {err}", path.display()));
+    log::info(format_args!("opened {} for reading", path.display()));
+    BufReader::with_capacity(buffer::capacity(path, &file), Retry(file))
 }
 
-fn open_files(paths: &[PathBuf]) -> SmallVec<[BufReader<File>; 4]> {
-    let mut files = SmallVec::with_capacity(paths.len());
-    let mut opts = OpenOptions::new();
-    opts.read(true);
-    for path in paths {
-        files.push(BufReader::new(
-            opts.open(path).expect("couldn't open for reading"),
-        ));
+/// `source` followed by the paths of any `--files-from` list, in the `--sort` order if any
+fn list_sources(
+    mut source: Vec<PathBuf>,
+    files_from: Option<&Path>,
+    sort: Option<sort::SortOrder>,
+) -> Vec<PathBuf> {
+    if let Some(list) = files_from {
+        source.extend(read_file_list(list));
     }
-    files
+    if let Some(order) = sort {
+        sort::sort(&mut source, order);
+    }
+    source
+}
+
+/// The paths listed in `list`, one per line, leaving out blank lines
+fn read_file_list(list: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for line in open_input(list).lines() {
+        let line = line.unwrap_or_else(|err| panic!("couldn't read {}: {err}", list.display()));
+        if !line.trim().is_empty() {
+            paths.push(PathBuf::from(line.trim_end_matches('\r')));
+        }
+    }
+    log::info(format_args!(
+        "{} lists {} sources",
+        list.display(),
+        paths.len()
+    ));
+    paths
+}
+
+/// A source file, or standard input
+type Input = Box<dyn BufRead>;
+
+/// What Windows editors like Notepad put at the start of UTF-8 files
+const BOM: &[u8] = "\u{feff}".as_bytes();
+
+/// Open `path`, or standard input for `-`, decompressing it if it's gzip, past any UTF-8 BOM
+fn open_input(path: &Path) -> Input {
+    let mut input: Input = if path.as_os_str() == "-" {
+        Box::new(std::io::stdin().lock())
+    } else {
+        open_rotated(path).unwrap_or_else(|| Box::new(open_file(path)))
+    };
+    report::input(path);
+    if gzip::is_gzip(&mut input) {
+        log::info(format_args!("decompressing {}", path.display()));
+        input = Box::new(BufReader::new(gzip::Decoder::new(input)));
+    }
+    if input.fill_buf().is_ok_and(|start| start.starts_with(BOM)) {
+        log::info(format_args!("skipping the BOM of {}", path.display()));
+        input.consume(BOM.len());
+    }
+    input
+}
+
+fn assert_one_stdin(paths: &[PathBuf]) {
+    assert!(
+        paths.iter().filter(|p| p.as_os_str() == "-").count() <= 1,
+        "only one source can be `-`, standard input"
+    );
 }
 
-fn gen_inner(mut w: impl Write, lines: usize) {
+fn open_files(paths: &[PathBuf]) -> SmallVec<[Input; 4]> {
+    assert_one_stdin(paths);
+    paths.iter().map(|path| open_input(path)).collect()
+}
+
+fn skip_lines(r: &mut impl BufRead, n: usize) {
+    let mut buf = Vec::new();
+    for _ in 0..n {
+        if r.read_until(b'\n', &mut buf).expect("couldn't read") == 0 {
+            break;
+        }
+        buf.clear();
+    }
+}
+
+/// Every line has to hold the `--bits` of entropy, `width` bytes
+fn check_width(entropy: &[u8], width: usize, file: Option<usize>, line: usize) {
+    if entropy.len() != width {
+        let mut failure = Failure::new(
+            "width",
+            format_args!(
+                "{} words ({} bits), but --bits {} lines have {} words",
+                entropy.len() * 3 / 4,
+                entropy.len() * 8,
+                width * 8,
+                width * 3 / 4
+            ),
+        )
+        .line(line);
+        failure.file = file;
+        failure.raise();
+    }
+}
+
+/// Write `lines` random lines of `N` bytes, or the same `repeat` line over and over,
+/// without end for 0 lines until Ctrl-C. Returns how many lines that was
+fn gen_inner<const N: usize>(
+    mut w: impl Write,
+    lines: usize,
+    style: &impl Encoding,
+    repeat: Option<&[u8]>,
+    checkpoint_every: usize,
+) -> usize {
     let rng = &mut OsRng;
-    let mut buf = [0u8; 32];
-    for _ in 0..lines {
-        buf.try_fill(rng).unwrap();
-        writeln!(&mut w, "{}", Mnemonic::from_entropy(&buf).unwrap()).unwrap();
+    let mut buf = [0u8; N];
+    if let Some(repeat) = repeat {
+        buf.copy_from_slice(repeat);
+    }
+    let endless = lines == 0;
+    let mut i = 0;
+    while if endless {
+        !interrupt::requested()
+    } else {
+        i < lines
+    } {
+        i += 1;
+        if repeat.is_none() {
+            buf.try_fill(rng).unwrap();
+        }
+        style.write(&mut w, i, &buf).unwrap();
+        checkpoint(&mut w, checkpoint_every, i);
+    }
+    log::debug(format_args!("generated {i} lines"));
+    report::lines(i);
+    i
+}
+
+/// A share being written, counting its lines to check it keeps in step with the source
+struct Paced<W: Write> {
+    inner: W,
+    newlines: usize,
+}
+
+impl<W: Write> Paced<W> {
+    const fn new(inner: W) -> Self {
+        Self { inner, newlines: 0 }
     }
 }
 
-fn split(Split { source, dest }: Split) {
-    let mut source = open_file(&source);
-    let mut new_files = create_files(&dest);
-    let (first, rest) = new_files.split_first_mut().expect("cli checked");
+impl<W: Write> Write for Paced<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.newlines += count::count_newlines(&buf[..len]);
+        Ok(len)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Flush `w` if `line` is a multiple of a nonzero `every`
+fn checkpoint(w: &mut impl Write, every: usize, line: usize) {
+    if every != 0 && line.is_multiple_of(every) {
+        w.flush()
+            .unwrap_or_else(|err| panic!("couldn't write: {err}"));
+    }
+}
+
+/// Paths of `total` `--shares` in `dir`, named after `template`
+// `{n}` and `{total}` are placeholders of `--name-template`
+#[allow(clippy::literal_string_with_formatting_args)]
+fn share_names(total: usize, template: &str, dir: &Path) -> Vec<PathBuf> {
+    assert!(
+        template.contains("{n}"),
+        "--name-template needs an `{{n}}` to tell the shares apart"
+    );
+    (1..=total)
+        .map(|n| {
+            dir.join(
+                template
+                    .replace("{n}", &n.to_string())
+                    .replace("{total}", &total.to_string()),
+            )
+        })
+        .collect()
+}
+
+/// Write a `.meta` next to each of the `dest` shares, with its own date of `not_before`
+fn write_metas(
+    dest: &[PathBuf],
+    style: &Style,
+    lines: usize,
+    not_before: &[date::Date],
+    checksum: Option<mac::Algorithm>,
+    output: Output,
+) {
+    for (k, path) in dest.iter().enumerate() {
+        let date = not_before.get(k).or_else(|| not_before.first()).copied();
+        meta::write(path, style, lines, date, checksum, output);
+    }
+}
+
+fn split(
+    Split {
+        source,
+        mut dest,
+        shares,
+        name_template,
+        output_dir,
+        framed,
+        interleave,
+        sheet,
+        pad_source,
+        byte_range,
+        checksum_share,
+        parity,
+        min_shares,
+        audit_source,
+        mac,
+        checksum_algorithm,
+        dump_entropy,
+        verify_written,
+        meta,
+        not_before,
+        range,
+        style,
+        output,
+    }: Split,
+) {
+    log::dump_entropy(dump_entropy);
+    report::style(&style);
+    if let Some(total) = shares {
+        dest = share_names(total, &name_template, &output_dir);
+    }
+    assert!(
+        dest.len() >= min_shares,
+        "refusing to split into {} shares, at least {min_shares} are required",
+        dest.len()
+    );
+    assert!(
+        not_before.len() <= 1 || not_before.len() == dest.len(),
+        "--not-before takes one date for all shares or one for each of the {}",
+        dest.len()
+    );
+    if let Some(pad_source) = &pad_source {
+        assert_one_stdin(&[source.clone(), pad_source.clone()]);
+    }
+    if let Some(mode) = audit_source {
+        audit::audit_source(&source, &style, range, mode);
+    }
+    let source_path = source;
+    let mut source = open_input(&source_path);
+    let mut pad_source = pad_source.as_deref().map(open_pad_source);
+    let mut checksums = checksum_share
+        .as_deref()
+        .map(|path| create_file(path, output));
+    if framed || interleave.is_some() {
+        split_to_stream(interleave.as_deref(), dest.len(), output, |shares| {
+            with_width!(style, N => split_inner::<N>(
+                &mut source,
+                shares,
+                range,
+                &style,
+                pad_source.as_mut(),
+                byte_range,
+                checksums.as_mut().map(|w| checksum::Writer::new(w, checksum_algorithm)),
+                output.checkpoint_every,
+            ));
+        });
+        if let Some(checksums) = checksums {
+            finish_file(checksums);
+        }
+        return;
+    }
+    let key = mac.as_deref().map(mac::read_key);
+    let size = source_size(&source_path);
+    let mut new_files: SmallVec<[_; 4]> = zip(create_files(&dest, output), &dest)
+        .map(|(file, path)| {
+            preallocate(&file, path, size, output);
+            mac::MacWriter::new(BufWriter::new(file), key.as_deref(), checksum_algorithm)
+        })
+        .collect();
+    let mut chunked: SmallVec<[_; 4]> = new_files
+        .iter_mut()
+        .map(|file| Chunked::new(file, output.chunk_lines))
+        .collect();
+    let lines = with_width!(style, N => split_inner::<N>(
+        &mut source,
+        &mut chunked,
+        range,
+        &style,
+        pad_source.as_mut(),
+        byte_range,
+        checksums.as_mut().map(|w| checksum::Writer::new(w, checksum_algorithm)),
+        output.checkpoint_every,
+    ));
+    for file in chunked {
+        file.finish();
+    }
+    for file in new_files {
+        finish_file(file.finish());
+    }
+    if let Some(checksums) = checksums {
+        finish_file(checksums);
+    }
+    if meta {
+        let checksum = (mac.is_some() || checksum_share.is_some()).then_some(checksum_algorithm);
+        write_metas(&dest, &style, lines, &not_before, checksum, output);
+    }
+    if verify_written {
+        verify_split(&source_path, &dest, &style, range, byte_range, lines);
+    }
+    if !parity.is_empty() {
+        parity::write(&dest, &parity, &style, output);
+    }
+    if let Some(sheet) = &sheet {
+        for (k, path) in dest.iter().enumerate() {
+            let sheet = sheet::name(sheet, k + 1);
+            sheet::write(path, k + 1, dest.len(), &sheet, &style, output);
+            log::info(format_args!(
+                "printed {} to {}",
+                path.display(),
+                sheet.display()
+            ));
+        }
+    }
+}
+
+/// `--verify-written` for `split`, each of the shares `dest` on its own and then all of them against the secret
+fn verify_split(
+    source: &Path,
+    dest: &[PathBuf],
+    style: &Style,
+    range: Range,
+    bytes: Option<ByteRange>,
+    lines: usize,
+) {
+    for (f, path) in dest.iter().enumerate() {
+        verify::lines(path, f, style, lines);
+    }
+    let mut inputs = open_files(dest);
+    let mut combined = Vec::new();
+    with_width!(style, N => xor_inner::<N>(
+        &mut combined,
+        &mut inputs,
+        Range::default(),
+        style,
+        false,
+        None,
+        bytes,
+        None,
+        None,
+    ));
+    verify::matches_source(source, &combined, style, range);
+    combined.fill(0);
+}
+
+/// Let `split` write `shares` shares to one stream, as frames or `--interleave`d into `interleave`
+///
+/// The stream is stdout unless `interleave` is a path other than `-`
+fn split_to_stream(
+    interleave: Option<&Path>,
+    shares: usize,
+    output: Output,
+    split: impl FnOnce(&mut [Box<dyn Write + '_>]),
+) {
+    let mut file = interleave
+        .filter(|path| path.as_os_str() != "-")
+        .map(|path| create_file(path, output));
+    let mut stdout = BufWriter::new(stdout().lock());
+    let out = file
+        .as_mut()
+        .map_or(&mut stdout as &mut dyn Write, |w| w as &mut dyn Write);
+    if interleave.is_some() {
+        let stream = std::cell::RefCell::new(interleave::Interleaver::new(out, shares));
+        let mut writers: SmallVec<[_; 4]> = (0..shares)
+            .map(|k| Box::new(interleave::ShareWriter::new(&stream, k)) as Box<dyn Write>)
+            .collect();
+        split(&mut writers);
+    } else {
+        let stream = std::cell::RefCell::new(out);
+        let mut writers: SmallVec<[_; 4]> = (0..shares)
+            .map(|n| Box::new(framed::FrameWriter::new(&stream, n)) as Box<dyn Write>)
+            .collect();
+        split(&mut writers);
+    }
+    stdout
+        .flush()
+        .unwrap_or_else(|err| panic!("couldn't write: {err}"));
+    if let Some(file) = file {
+        finish_file(file);
+    }
+}
+
+/// Open a `--pad-source`, where `fdN` is file descriptor N
+fn open_pad_source(path: &Path) -> Input {
+    let fd = path
+        .to_str()
+        .and_then(|p| p.strip_prefix("fd")?.parse::<u32>().ok());
+    // pads are binary, so none of the BOM and gzip sniffing of `open_input`
+    report::input(path);
+    match fd {
+        Some(fd) => Box::new(open_file(Path::new(&format!("/dev/fd/{fd}")))),
+        None if path.as_os_str() == "-" => Box::new(std::io::stdin().lock()),
+        None => Box::new(open_file(path)),
+    }
+}
+
+/// Write the secret XOR all pads to the first of `dests`, and a pad to each other one
+///
+/// The pads are random, or read from `pad_source` if there is one.
+/// Every source line becomes exactly one entry in each of `dests`, which is what lets `xor`
+/// line them up again, and falling out of step with it fails right away.
+/// Returns how many lines each got
+// each option of `split` that changes how lines are split comes in on its own
+#[allow(clippy::too_many_arguments)]
+fn split_inner<const N: usize>(
+    source: &mut impl BufRead,
+    dests: &mut [impl Write],
+    range: Range,
+    style: &impl Encoding,
+    mut pad_source: Option<&mut Input>,
+    bytes: Option<ByteRange>,
+    mut checksums: Option<checksum::Writer>,
+    checkpoint_every: usize,
+) -> usize {
+    if let Some(bytes) = bytes {
+        bytes.check(N);
+    }
+    let mut paced: SmallVec<[_; 4]> = dests.iter_mut().map(Paced::new).collect();
+    let (first, rest) = paced.split_first_mut().expect("cli checked");
     let mut str = String::new();
-    let mut bufs: SmallVec<[[u8; 32]; 3]> = smallvec![[0; 32]; rest.len()];
-    let mut i = 0;
+    // one random fill per line for all the pads, instead of one per share
+    let mut pads: SmallVec<[u8; 96]> = smallvec![0; N * rest.len()];
+    skip_lines(source, range.read_past());
+    let mut i = range.skip();
     let rng = &mut OsRng;
-    while source.read_line(&mut str).unwrap() != 0 {
-        let src = match Mnemonic::parse_in_normalized(English, &str) {
+    while !range.done(i) && read_data_line(source, &mut str, None, i) != 0 {
+        let mut src = match style.decode_secret(&str, i + 1) {
             Ok(src) => src,
-            Err(e) => panic!("error on line {i}: {e}"),
+            Err(e) => Failure::new("parse", e).line(i).raise(),
         };
         str.clear();
+        check_width(&src, N, None, i);
         i += 1;
-        let (mut src, len) = src.to_entropy_array();
-        assert_eq!(len, 32);
-        let src: &mut [u8; 32] = (&mut src[..32]).try_into().unwrap();
-        for (buf, file) in zip(&mut bufs, &mut *rest) {
-            buf.try_fill(rng).unwrap();
-            for (s, b) in zip(&mut *src, &*buf) {
+        let src: &mut [u8; N] = (&mut src[..]).try_into().unwrap();
+        if let Some(checksums) = &mut checksums {
+            checksums
+                .write(src)
+                .unwrap_or_else(|err| panic!("couldn't write: {err}"));
+        }
+        if let Some(pad_source) = &mut pad_source {
+            pad_source.read_exact(&mut pads).unwrap_or_else(|err| {
+                Failure::new("pads", format_args!("--pad-source ran out: {err}"))
+                    .line(i - 1)
+                    .raise()
+            });
+        } else {
+            pads[..].try_fill(rng).unwrap();
+        }
+        if let Some(bytes) = bytes {
+            for pad in pads.chunks_exact_mut(N) {
+                bytes.copy_outside(pad, &[0; N]);
+            }
+        }
+        if log::dumping(i - 1 - range.skip()) {
+            log::entropy(i - 1, format_args!("source"), src);
+            for (n, pad) in pads.chunks_exact(N).enumerate() {
+                log::entropy(i - 1, format_args!("pad {}", n + 1), pad);
+            }
+        }
+        for (pad, file) in zip(pads.chunks_exact(N), &mut *rest) {
+            for (s, b) in zip(&mut *src, pad) {
                 *s ^= b;
             }
-            writeln!(file, "{}", Mnemonic::from_entropy(buf).unwrap()).unwrap();
+            style.write(&mut *file, i - range.skip(), pad).unwrap();
+        }
+        if log::dumping(i - 1 - range.skip()) {
+            log::entropy(i - 1, format_args!("share 0"), src);
+        }
+        style.write(&mut *first, i - range.skip(), src).unwrap();
+        checkpoint(&mut *first, checkpoint_every, i);
+        for file in &mut *rest {
+            checkpoint(file, checkpoint_every, i);
+        }
+        let expected = (i - range.skip()) * style.lines_per_entry();
+        let off_pace = std::iter::once(&*first)
+            .chain(&*rest)
+            .map(|share| share.newlines)
+            .enumerate()
+            .find(|&(_, lines)| lines != expected);
+        if let Some((k, lines)) = off_pace {
+            Failure::new(
+                "pace",
+                format_args!(
+                    "share {} has {lines} lines where it should have {expected}",
+                    k + 1
+                ),
+            )
+            .line(i - 1)
+            .raise();
         }
-        writeln!(first, "{}", Mnemonic::from_entropy(src).unwrap()).unwrap();
     }
+    log::debug(format_args!(
+        "split {} lines into {} shares",
+        i - range.skip(),
+        paced.len()
+    ));
+    report::lines(i - range.skip());
+    range.finish(i);
+    i - range.skip()
 }
 
-fn xor(Xor { source, dest }: Xor) {
-    let mut inputs = open_files(&source);
-    if let Some(path) = dest {
-        xor_inner(create_file(&path), &mut inputs);
+/// Check `schedule` against the number of `sources`,
+/// and warn about its groups that leave out the `--recover` position
+fn check_schedule(schedule: &schedule::Schedule, recover: Option<usize>, sources: usize) {
+    schedule.check(sources);
+    let Some(source) = recover else {
+        return;
+    };
+    assert!(
+        source < sources,
+        "--recover {source} isn't one of the {sources} sources, counting from 0"
+    );
+    let groups = schedule.without(source);
+    if !groups.is_empty() {
+        let groups: Vec<_> = groups.iter().map(ToString::to_string).collect();
+        log::warn(format_args!(
+            "groups {} of --schedule, counting from 0, leave out source {source}, \
+             their lines won't come out as the lost source's",
+            groups.join(", ")
+        ));
+    }
+}
+
+fn xor(
+    Xor {
+        source,
+        files_from,
+        sort,
+        dest,
+        mut range,
+        style,
+        output,
+        preview,
+        fold,
+        detect_misalignment,
+        promote,
+        schedule,
+        recover,
+        byte_range,
+        with_mnemonic,
+        checksum_share,
+        parse_threads,
+        deinterleave,
+        from_tar,
+        allow_single_input,
+        respect_time_lock,
+        clipboard,
+        clear_after,
+        mac,
+        checksum_algorithm,
+        dump_entropy,
+    }: Xor,
+) {
+    log::dump_entropy(dump_entropy);
+    let dest = dest.filter(|path| path.as_os_str() != "-");
+    let source = list_sources(source, files_from.as_deref(), sort);
+    let mut tarred = from_tar.map(|archive| tar::entries(&archive));
+    let count = tarred.as_ref().map_or(source.len(), |tar| tar.inputs.len());
+    check_source_count(count, allow_single_input || deinterleave.is_some());
+    let sidecars = tarred.as_mut().map_or_else(
+        || meta::of(&source),
+        |tar| std::mem::take(&mut tar.sidecars),
+    );
+    meta::check_time_lock(&sidecars, respect_time_lock);
+    let checksum_algorithm = meta::checksum_algorithm(&sidecars, checksum_algorithm);
+    if let Some(key) = &mac {
+        mac::verify(&source, &mac::read_key(key), checksum_algorithm, 0);
+    }
+    let style = meta::configure_with(&sidecars, style, &mut range);
+    report::style(&style);
+    let constant = with_mnemonic.map(|words| style.parse_mnemonic("--with-mnemonic", &words));
+    if let Some(schedule) = &schedule {
+        check_schedule(schedule, recover, count);
+    }
+    if detect_misalignment {
+        warn_misaligned(&source, &style);
+    }
+    if fold && !preview {
+        return xor_fold(
+            &source,
+            dest.as_deref(),
+            range,
+            &style,
+            output,
+            promote,
+            byte_range,
+        );
+    }
+    if preview {
+        return preview_inputs(&source, &style);
+    }
+    let (mut inputs, sizes) = match tarred {
+        Some(tar) => (tar.inputs, tar.sizes),
+        None => (
+            open_sources(&source, parse_threads, deinterleave),
+            vec![source_size(&source[0])],
+        ),
+    };
+    for file in &mut inputs {
+        skip_lines(file, range.read_past());
+    }
+    let mut verifier = checksum_share
+        .as_deref()
+        .map(|path| checksum::Verifier::open(path, range.skip(), checksum_algorithm));
+    if clipboard {
+        let mut secret = Vec::new();
+        with_width!(style, N => xor_inner::<N>(
+            &mut secret,
+            &mut inputs,
+            range,
+            &style,
+            promote,
+            schedule.as_ref(),
+            byte_range,
+            verifier.as_mut(),
+            constant.as_deref(),
+        ));
+        if let Some(verifier) = verifier.take() {
+            verifier.finish();
+        }
+        clipboard::copy_line(secret, clear_after);
     } else {
-        xor_inner(stdout().lock(), &mut inputs);
+        let mut file = dest.map(|path| {
+            let w = create_file(&path, output);
+            preallocate(w.get_ref(), &path, sizes[0], output);
+            w
+        });
+        let mut stdout = stdout().lock();
+        let mut w = Chunked::new(
+            file.as_mut()
+                .map_or(&mut stdout as &mut dyn Write, |w| w as &mut dyn Write),
+            output.chunk_lines,
+        );
+        with_width!(style, N => xor_inner::<N>(
+            &mut w,
+            &mut inputs,
+            range,
+            &style,
+            promote,
+            schedule.as_ref(),
+            byte_range,
+            verifier.as_mut(),
+            constant.as_deref(),
+        ));
+        w.finish();
+        if let Some(file) = file {
+            finish_file(file);
+        }
+    }
+    if let Some(verifier) = verifier {
+        verifier.finish();
+    }
+}
+
+/// Open each of `source`, or the shares of the one with `--deinterleave`
+fn open_sources(
+    source: &[PathBuf],
+    parse_threads: bool,
+    deinterleave: Option<usize>,
+) -> SmallVec<[Input; 4]> {
+    match deinterleave {
+        Some(shares) => {
+            assert_eq!(
+                source.len(),
+                1,
+                "--deinterleave takes all the shares from a single source"
+            );
+            interleave::deinterleave(&source[0], shares)
+        }
+        None if parse_threads => {
+            assert_one_stdin(source);
+            source
+                .iter()
+                .map(|path| Box::new(prefetch::Prefetch::spawn(path.clone())) as Input)
+                .collect()
+        }
+        None => open_files(source),
+    }
+}
+
+/// Refuse fewer than 2 sources, except for 1 with `--allow-single-input`
+fn check_source_count(count: usize, allow_single_input: bool) {
+    assert!(
+        count != 1 || allow_single_input,
+        "xor of a single source is just a copy of it, pass --allow-single-input if that's really meant"
+    );
+    assert!(count != 0, "xor needs at least 2 sources, none given");
+}
+
+fn xor_fold(
+    source: &[PathBuf],
+    dest: Option<&Path>,
+    range: Range,
+    style: &Style,
+    output: Output,
+    promote: bool,
+    bytes: Option<ByteRange>,
+) {
+    let dir = dest
+        .and_then(Path::parent)
+        .map_or_else(std::env::temp_dir, Path::to_path_buf);
+    assert_one_stdin(source);
+    let mut acc = open_input(&source[0]);
+    skip_lines(&mut acc, range.read_past());
+    let mut acc_path: Option<FoldTemp>;
+    // temporary files keep the numbers of the lines they came from, for `--index`
+    let numbered = Renumbered(style, range.skip());
+    for (n, path) in source.iter().enumerate().skip(1) {
+        let mut next = open_input(path);
+        skip_lines(&mut next, range.read_past());
+        let mut inputs = [acc, next];
+        let tmp = if n + 1 == source.len() {
+            if let Some(path) = dest {
+                let mut w = create_file(path, output);
+                with_width!(style, N => xor_inner::<N>(&mut w, &mut inputs, range, style, promote, None, bytes, None, None));
+                finish_file(w);
+            } else {
+                with_width!(style, N => xor_inner::<N>(stdout().lock(), &mut inputs, range, style, promote, None, bytes, None, None));
+            }
+            None
+        } else {
+            let tmp = FoldTemp(dir.join(format!(".xoriaz-fold-{}-{n}", std::process::id())));
+            let mut w = create_file(&tmp.0, Output::default());
+            with_width!(style, N => xor_inner::<N>(&mut w, &mut inputs, range, &numbered, promote, None, bytes, None, None));
+            finish_file(w);
+            Some(tmp)
+        };
+        drop(inputs);
+        // the previous one goes as soon as it's read, dropping it zeroes and removes it
+        acc_path = tmp;
+        let Some(tmp) = &acc_path else { break };
+        acc = Box::new(open_file(&tmp.0));
+    }
+}
+
+/// A temporary file of `xor --fold`, which holds part of the secret
+///
+/// It's zeroed and removed when dropped, also when a later pass fails
+struct FoldTemp(PathBuf);
+
+impl Drop for FoldTemp {
+    fn drop(&mut self) {
+        let path = &self.0;
+        let zeroed = std::fs::OpenOptions::new()
+            .write(true)
+            .open(os_path(path))
+            .and_then(|mut file| {
+                let len = file.metadata()?.len();
+                std::io::copy(&mut std::io::repeat(0).take(len), &mut file)?;
+                file.sync_all()
+            });
+        if let Err(err) = zeroed {
+            log::warn(format_args!("couldn't zero {}: {err}", path.display()));
+        }
+        match std::fs::remove_file(os_path(path)) {
+            Ok(()) => log::info(format_args!("removed temporary {}", path.display())),
+            Err(err) => log::warn(format_args!("couldn't remove {}: {err}", path.display())),
+        }
+    }
+}
+
+/// `style`, but writing line `n` as line `n` plus the offset
+struct Renumbered<'a, E>(&'a E, usize);
+
+impl<E: Encoding> Encoding for Renumbered<'_, E> {
+    fn decode(&self, line: &str, n: usize) -> Result<format::Entropy, format::Error> {
+        self.0.decode(line, n)
+    }
+    fn write(&self, w: impl Write, n: usize, entropy: &[u8]) -> std::io::Result<()> {
+        self.0.write(w, n + self.1, entropy)
     }
 }
 
-fn xor_inner(mut w: impl Write, inputs: &mut [BufReader<File>]) {
-    let buf = &mut [0u8; 32];
+/// XOR `inputs` line by line into `w`, zero-extending narrower lines if `promote`
+///
+/// With a `schedule`, each line only gets the sources of its group.
+/// A `verifier` checks every line that comes out, after the `constant` is mixed into it
+// each option of `xor` that changes how lines are combined comes in on its own
+#[allow(clippy::too_many_arguments)]
+fn xor_inner<const N: usize>(
+    mut w: impl Write,
+    inputs: &mut [impl BufRead],
+    range: Range,
+    style: &impl Encoding,
+    promote: bool,
+    schedule: Option<&schedule::Schedule>,
+    bytes: Option<ByteRange>,
+    mut verifier: Option<&mut checksum::Verifier>,
+    constant: Option<&[u8]>,
+) {
+    if let Some(bytes) = bytes {
+        bytes.check(N);
+    }
+    if let Some(constant) = constant {
+        assert!(
+            constant.len() == N || promote && constant.len() < N,
+            "--with-mnemonic holds {} bytes, but the lines {N}",
+            constant.len()
+        );
+    }
+    let mut acc = XorAccumulator::<N>::new();
+    // the first source, for what's outside of `bytes`
+    let mut first = [0; N];
+    let mut width = 0;
     let str = &mut String::new();
-    let mut i = 0;
-    let mut f;
+    let mut i = range.skip();
+    let (mut f, mut mixed);
     let mut finishing = false;
     loop {
-        f = 0;
+        if range.done(i) {
+            break;
+        }
+        (f, mixed) = (0, 0);
         for file in &mut *inputs {
-            let len = file.read_line(str).expect("couldn't read");
+            let len = read_data_line(file, str, Some(f), i);
             if len == 0 {
                 if f == 0 {
                     finishing = true;
                 }
-                assert!(finishing, "file {f} suddenly ended");
+                if !finishing {
+                    Failure::new("length", "suddenly ended")
+                        .file(f)
+                        .line(i)
+                        .raise();
+                }
                 continue;
             }
-            assert!(!finishing, "file {f} continues longer than previous file");
-            let m = match Mnemonic::parse_in_normalized(English, str) {
+            if finishing {
+                Failure::new("length", "continues longer than previous file")
+                    .file(f)
+                    .line(i)
+                    .raise();
+            }
+            let m = match style.decode(str, i + 1) {
                 Ok(m) => m,
-                Err(e) => panic!("error on line {i} in file {f}: {e}"),
+                Err(e) => Failure::new("parse", e).file(f).line(i).raise(),
             };
             str.clear();
-            let (m, len) = m.to_entropy_array();
-            assert_eq!(len, 32);
-            let m: &[u8; 32] = (&m[..32]).try_into().unwrap();
-            zip(&mut *buf, m).for_each(|(s, b)| *s ^= b);
+            if f == 0 && bytes.is_some() {
+                let len = m.len().min(N);
+                first = [0; N];
+                first[..len].copy_from_slice(&m[..len]);
+            }
+            if schedule.is_some_and(|s| !s.includes(i - range.skip(), f)) {
+                f += 1;
+                continue;
+            }
+            mix_line(&mut acc, &m, promote, f, i);
+            mixed += 1;
+            width = if promote { width.max(m.len()) } else { m.len() };
+            if log::dumping(i - range.skip()) {
+                log::entropy(i, format_args!("source {}", f + 1), &m);
+                log::entropy(i, format_args!("running"), &acc.finish()[..width]);
+            }
             f += 1;
         }
         if finishing {
             break;
         }
-        writeln!(w, "{}", Mnemonic::from_entropy(buf).unwrap()).unwrap();
-        *buf = [0; 32];
+        // every source of the line has to be in it, or the result is garbage
+        let expected = schedule.map_or(inputs.len(), |s| s.sources(i - range.skip()));
+        check_sources(mixed, expected, i);
+        let mut out = acc.finish();
+        if let Some(constant) = constant {
+            for (o, c) in zip(&mut out, constant) {
+                *o ^= c;
+            }
+            width = width.max(constant.len());
+        }
+        if let Some(bytes) = bytes {
+            bytes.copy_outside(&mut out, &first);
+        }
+        if let Some(verifier) = &mut verifier {
+            verifier.check(i, &out[..width]);
+        }
+        style
+            .write(&mut w, i - range.skip() + 1, &out[..width])
+            .unwrap();
+        if i == range.skip() {
+            log::debug(format_args!("lines are {width} bytes wide"));
+        }
+        acc.reset();
+        width = 0;
         i += 1;
     }
+    log::debug(format_args!(
+        "combined {} lines of {} sources",
+        i - range.skip(),
+        inputs.len()
+    ));
+    report::lines(i - range.skip());
+    range.finish(i);
+}
+
+/// Fail unless all the `expected` sources of line `i` were mixed into it
+fn check_sources(mixed: usize, expected: usize, i: usize) {
+    if mixed != expected {
+        Failure::new(
+            "sources",
+            format_args!("only {mixed} of the {expected} sources of the line went into it"),
+        )
+        .line(i)
+        .raise();
+    }
+}
+
+/// Mix line `i` of source `f` into `acc`, zero-extended if `promote` and narrower
+fn mix_line<const N: usize>(
+    acc: &mut XorAccumulator<N>,
+    m: &[u8],
+    promote: bool,
+    f: usize,
+    i: usize,
+) {
+    if promote && m.len() <= N {
+        let mut padded = [0; N];
+        padded[..m.len()].copy_from_slice(m);
+        acc.mix(&padded);
+    } else {
+        check_width(m, N, Some(f), i);
+        acc.mix(m);
+    }
 }
 
-fn check(Check { source }: Check) {
+/// Compare the line counts of `paths`, and look for junk lines at their starts
+///
+/// Standard input can only be read once, so it's left out
+fn warn_misaligned(paths: &[PathBuf], style: &Style) {
+    const MAX_SHIFT: usize = 3;
+    let scans: SmallVec<[_; 4]> = paths
+        .iter()
+        .map(|path| (path.as_os_str() != "-").then(|| scan_lines(path, style)))
+        .collect();
+    let counts = || scans.iter().flatten().map(|&(lines, _)| lines);
+    let Some(reference) = counts().max_by_key(|&n| counts().filter(|&m| m == n).count()) else {
+        return;
+    };
+    for (f, (scan, path)) in zip(&scans, paths).enumerate() {
+        let Some((lines, junk)) = *scan else { continue };
+        if (1..=MAX_SHIFT).contains(&junk) {
+            log::warn(format_args!(
+                "file {} ({}) may be offset by {junk} lines, its first {junk} aren't valid lines",
+                f + 1,
+                path.display()
+            ));
+        } else if (1..=MAX_SHIFT).contains(&lines.abs_diff(reference)) {
+            log::warn(format_args!(
+                "file {} ({}) may be offset by {} lines, it has {lines} lines where most have {reference}",
+                f + 1,
+                path.display(),
+                lines.abs_diff(reference)
+            ));
+        }
+    }
+}
+
+/// Count the lines in `path`, and how many at the start don't decode
+fn scan_lines(path: &Path, style: &Style) -> (usize, usize) {
+    let mut file = open_input(path);
+    let str = &mut String::new();
+    let (mut lines, mut junk) = (0, 0);
+    while file.read_line(str).expect("couldn't read") != 0 {
+        if lines == junk && style.decode(str, lines + 1).is_err() {
+            junk += 1;
+        }
+        lines += 1;
+        str.clear();
+    }
+    (lines, junk)
+}
+
+/// Read a line into `str` like [`BufRead::read_line`], but stop before a `mac:` line too,
+/// or before blank lines that only run to the end, as some editors add
+///
+/// A last line without a newline is returned like any other, and only a
+/// read of nothing at all counts as the end, so it's never lost or read twice.
+fn read_data_line(file: &mut impl BufRead, str: &mut String, f: Option<usize>, i: usize) -> usize {
+    let len = read_text_line(file, str, f, i);
+    if str.starts_with(mac::PREFIX) {
+        str.clear();
+        return 0;
+    }
+    if len != 0 && str.trim().is_empty() {
+        let rest = &mut String::new();
+        loop {
+            rest.clear();
+            if read_text_line(file, rest, f, i) == 0 || rest.starts_with(mac::PREFIX) {
+                str.clear();
+                return 0;
+            }
+            if !rest.trim().is_empty() {
+                let mut failure = Failure::new("parse", "blank line in the middle").line(i);
+                failure.file = f;
+                failure.raise();
+            }
+        }
+    }
+    len
+}
+
+/// [`BufRead::read_line`], but saying where in the line it stops being UTF-8
+fn read_text_line(file: &mut impl BufRead, str: &mut String, f: Option<usize>, i: usize) -> usize {
+    let mut bytes = std::mem::take(str).into_bytes();
+    let start = bytes.len();
+    let len = file.read_until(b'\n', &mut bytes).expect("couldn't read");
+    match String::from_utf8(bytes) {
+        Ok(line) => *str = line,
+        Err(err) => {
+            let at = err.utf8_error().valid_up_to() - start;
+            let mut failure = Failure::new(
+                "encoding",
+                format_args!("byte {at} of the line starts invalid UTF-8"),
+            )
+            .line(i);
+            failure.file = f;
+            failure.raise();
+        }
+    }
+    len
+}
+
+/// Short, non-reversible stand-in for a line's entropy
+fn digest(entropy: &[u8]) -> String {
+    sha256::Hash::hash(entropy)[..8].to_hex()
+}
+
+/// Print the line count and digests of the first and last lines of each of `paths`
+///
+/// Only the first lines are read through. Files on disk are then counted without decoding
+/// and read again from near their end, anything else is read through once
+fn preview_inputs(paths: &[PathBuf], style: &Style) {
+    const SHOWN: usize = 3;
+    for (f, path) in paths.iter().enumerate() {
+        let mut file = open_input(path);
+        let str = &mut String::new();
+        let mut head = SmallVec::<[String; SHOWN]>::new();
+        while head.len() < SHOWN && file.read_line(str).expect("couldn't read") != 0 {
+            head.push(std::mem::take(str));
+        }
+        // what's decompressed can't be seeked in
+        let seekable = path.as_os_str() != "-"
+            && File::open(os_path(path)).is_ok_and(|raw| {
+                raw.metadata().is_ok_and(|m| m.is_file())
+                    && !gzip::is_gzip(&mut BufReader::new(raw))
+            });
+        let (rest, tail) = if seekable {
+            let rest = count::count_lines(&mut file).expect("couldn't read");
+            (rest, last_lines(path, rest.min(SHOWN)))
+        } else {
+            let mut tail = VecDeque::with_capacity(SHOWN);
+            let mut rest = 0;
+            while file.read_line(str).expect("couldn't read") != 0 {
+                if tail.len() == SHOWN {
+                    tail.pop_front();
+                }
+                tail.push_back(std::mem::take(str));
+                rest += 1;
+            }
+            (rest, tail.into())
+        };
+        let lines = head.len() + rest;
+        let shown = |i: usize, line: &str| match style.decode(line, i + 1) {
+            Ok(m) => format!("line {}: {}", i + 1, digest(&m)),
+            Err(e) => format!("line {}: error: {e}", i + 1),
+        };
+        println!("file {}: {} ({lines} lines)", f + 1, path.display());
+        for (i, line) in head.iter().enumerate() {
+            println!("  {}", shown(i, line));
+        }
+        if lines > 2 * SHOWN {
+            println!("  ...");
+        }
+        for (k, line) in tail.iter().enumerate() {
+            println!("  {}", shown(lines - tail.len() + k, line));
+        }
+    }
+}
+
+/// The last `n` lines of the file at `path`, read from a seek to near its end
+fn last_lines(path: &Path, n: usize) -> Vec<String> {
+    let mut file = File::open(os_path(path))
+        .unwrap_or_else(|err| panic!("couldn't open {}: {err}", path.display()));
+    let len = file.metadata().map_or(0, |meta| meta.len());
+    let mut window = 4096;
+    loop {
+        let start = len.saturating_sub(window);
+        let mut end = Vec::new();
+        file.seek(SeekFrom::Start(start))
+            .and_then(|_| file.read_to_end(&mut end))
+            .unwrap_or_else(|err| panic!("couldn't read {}: {err}", path.display()));
+        let text = String::from_utf8_lossy(&end);
+        let lines: Vec<_> = text.lines().collect();
+        // the first line of the window can be cut off, unless it starts the file
+        if start == 0 || lines.len() > n {
+            return lines[lines.len().saturating_sub(n)..]
+                .iter()
+                .map(|line| (*line).to_owned())
+                .collect();
+        }
+        window *= 4;
+    }
+}
+
+fn check(
+    Check {
+        source,
+        mac,
+        checksum_algorithm,
+        every_nth,
+        respect_time_lock,
+        style,
+    }: Check,
+) {
+    let sidecars = meta::of(&source);
+    meta::check_time_lock(&sidecars, respect_time_lock);
+    let checksum_algorithm = meta::checksum_algorithm(&sidecars, checksum_algorithm);
+    if let Some(key) = &mac {
+        mac::verify(&source, &mac::read_key(key), checksum_algorithm, 1);
+    }
+    let style = meta::configure_with(&sidecars, style, &mut Range::default());
+    report::style(&style);
     let mut inputs = open_files(&source);
 
-    let inputs: &mut [BufReader<File>] = &mut inputs;
-    let buf = &mut [0u8; 32];
+    let inputs: &mut [Input] = &mut inputs;
+    let mut acc = XorAccumulator::<32>::new();
     let str = &mut String::new();
     let mut i = 0;
     let mut f;
@@ -226,35 +2302,466 @@ fn check(Check { source }: Check) {
     loop {
         f = 0;
         for file in &mut *inputs {
-            let len = file.read_line(str).expect("couldn't read");
+            let len = read_data_line(file, str, Some(f), i);
             if len == 0 {
                 if f == 0 {
                     finishing = true;
                 }
-                assert!(finishing, "file {f} suddenly ended");
+                if !finishing {
+                    Failure::new("length", "suddenly ended")
+                        .file(f)
+                        .line(i)
+                        .raise();
+                }
+                continue;
+            }
+            if finishing {
+                Failure::new("length", "continues longer than previous file")
+                    .file(f)
+                    .line(i)
+                    .raise();
+            }
+            if every_nth.is_some_and(|k| !i.is_multiple_of(k.get())) {
+                str.clear();
+                f += 1;
                 continue;
             }
-            assert!(!finishing, "file {f} continues longer than previous file");
 
-            let m = match Mnemonic::parse_in_normalized(English, str) {
+            let m = match style.decode(str, i + 1) {
                 Ok(m) => m,
-                Err(e) => panic!("error on line {i} in file {f}: {e}"),
+                Err(e) => Failure::new("parse", e).file(f).line(i).raise(),
             };
             str.clear();
-            let (m, len) = m.to_entropy_array();
-            assert_eq!(len, 32);
-            let m: &[u8; 32] = (&m[..32]).try_into().unwrap();
-            zip(&mut *buf, m).for_each(|(s, b)| *s ^= b);
+            check_width(&m, style.bytes(), Some(f), i);
+            let mut padded = [0; 32];
+            padded[..m.len()].copy_from_slice(&m);
+            acc.mix(&padded);
             f += 1;
         }
-        if buf.iter().any(|&x| x != 0) {
-            eprintln!("difference on line {i}");
-            std::process::exit(1);
+        if !acc.is_zero() {
+            eprintln!("difference on line {}", i + 1);
+            report::exit(1);
         }
         if finishing {
             break;
         }
+        acc.reset();
         i += 1;
     }
-    eprintln!("everything is awesome");
+    if let Some(k) = every_nth {
+        eprintln!(
+            "everything sampled is awesome, but that's only 1 in {k} lines, {} of {i}",
+            i.div_ceil(k.get())
+        );
+    } else {
+        eprintln!("everything is awesome");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The [`Failure`] that `run` raises
+    pub fn failure(run: impl FnOnce()) -> Failure {
+        let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(run))
+            .expect_err("should have failed");
+        *payload.downcast::<Failure>().expect("a Failure")
+    }
+
+    /// A path in the temp directory that nothing else uses, with nothing at it yet
+    pub fn scratch(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("xoriaz-test-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_dir_all(&path);
+        path
+    }
+
+    /// A pipe that delivers a few bytes at a time
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    fn xor_128(inputs: &[&str], schedule: Option<&schedule::Schedule>) -> String {
+        let style = Style {
+            bits: 128,
+            ..Style::default()
+        };
+        let mut inputs: Vec<&[u8]> = inputs.iter().map(|s| s.as_bytes()).collect();
+        let mut out = Vec::new();
+        xor_inner::<16>(
+            &mut out,
+            &mut inputs,
+            Range::default(),
+            &style,
+            false,
+            schedule,
+            None,
+            None,
+            None,
+        );
+        String::from_utf8(out).unwrap()
+    }
+
+    const ZEROS: &str = "abandon abandon abandon abandon abandon abandon \
+                         abandon abandon abandon abandon abandon about\n";
+    const ONES: &str = "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong\n";
+
+    #[test]
+    fn gen_bytes_is_never_zero() {
+        assert!(parse_entropy_size("0").is_err());
+        assert!(parse_entropy_size("0K").is_err());
+        assert_eq!(parse_entropy_size("1K"), Ok(1024));
+    }
+
+    #[test]
+    fn narrow_lines_xor_only_their_own_bytes() {
+        let style = Style {
+            bits: 128,
+            ..Style::default()
+        };
+        assert_eq!(style.decode(ZEROS, 1).unwrap().len(), 16);
+        assert_eq!(xor_128(&[ZEROS, ONES], None), ONES);
+    }
+
+    #[test]
+    fn plain_secret_splits_into_indexed_shares() {
+        let style = Style {
+            bits: 128,
+            index: true,
+            ..Style::default()
+        };
+        let plain = Style {
+            index: false,
+            ..style.clone()
+        };
+        let (mut secret, mut indexed) = (Vec::new(), Vec::new());
+        for (n, byte) in (1..=3).zip([0x11, 0x22, 0x33]) {
+            plain.write(&mut secret, n, &[byte; 16]).unwrap();
+            style.write(&mut indexed, n, &[byte; 16]).unwrap();
+        }
+        let mut shares = vec![Vec::new(); 3];
+        let lines = split_inner::<16>(
+            &mut &secret[..],
+            &mut shares,
+            Range::default(),
+            &style,
+            None,
+            None,
+            None,
+            0,
+        );
+        assert_eq!(lines, 3);
+        for share in &shares {
+            let line = std::str::from_utf8(share).unwrap().lines().nth(2).unwrap();
+            assert!(line.starts_with("3\t"), "{line}");
+        }
+        let mut inputs: Vec<&[u8]> = shares.iter().map(Vec::as_slice).collect();
+        let mut out = Vec::new();
+        xor_inner::<16>(
+            &mut out,
+            &mut inputs,
+            Range::default(),
+            &style,
+            false,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(out, indexed);
+    }
+
+    #[test]
+    fn blank_line_in_the_middle_fails() {
+        let gap = format!("{ONES}\n{ONES}");
+        let err = failure(|| {
+            xor_128(&[&ZEROS.repeat(3), &gap], None);
+        });
+        assert_eq!((err.kind, err.file, err.line), ("parse", Some(1), Some(1)));
+        assert_eq!(err.message, "blank line in the middle");
+    }
+
+    #[test]
+    fn schedule_counts_only_its_sources() {
+        let schedule = "0,1;1,2,2".parse().unwrap();
+        let out = xor_128(
+            &[&ONES.repeat(2), &ZEROS.repeat(2), &ONES.repeat(2)],
+            Some(&schedule),
+        );
+        assert_eq!(out, [ONES, ONES].concat());
+    }
+
+    #[test]
+    fn last_line_without_newline() {
+        let (str, mut file) = (&mut String::new(), &b"a\nb"[..]);
+        assert_eq!(read_data_line(&mut file, str, None, 0), 2);
+        assert_eq!(str, "a\n");
+        str.clear();
+        assert_eq!(read_data_line(&mut file, str, None, 1), 1);
+        assert_eq!(str, "b");
+        str.clear();
+        assert_eq!(read_data_line(&mut file, str, None, 2), 0);
+        let unterminated = ZEROS.trim_end();
+        assert_eq!(xor_128(&[unterminated, ONES], None), ONES);
+        let style = Style {
+            bits: 128,
+            ..Style::default()
+        };
+        let mut shares = vec![Vec::new(); 2];
+        let lines = split_inner::<16>(
+            &mut unterminated.as_bytes(),
+            &mut shares,
+            Range::default(),
+            &style,
+            None,
+            None,
+            None,
+            0,
+        );
+        assert_eq!(lines, 1);
+        assert!(shares.iter().all(|share| share.ends_with(b"\n")));
+    }
+
+    #[test]
+    fn only_a_newline_or_nothing_has_no_lines() {
+        let str = &mut String::new();
+        assert_eq!(read_data_line(&mut &b"\n"[..], str, None, 0), 0);
+        assert_eq!(str, "");
+        assert_eq!(read_text_line(&mut &b""[..], str, None, 0), 0);
+        assert_eq!(read_data_line(&mut &b""[..], str, None, 0), 0);
+        assert_eq!(xor_128(&["\n", ""], None), "");
+    }
+
+    #[test]
+    fn schedule_is_its_own_inverse() {
+        let style = Style {
+            bits: 128,
+            ..Style::default()
+        };
+        let lines = |bytes: [u8; 3]| {
+            let mut out = Vec::new();
+            for (n, byte) in (1..).zip(bytes) {
+                style.write(&mut out, n, &[byte; 16]).unwrap();
+            }
+            String::from_utf8(out).unwrap()
+        };
+        let sources = [lines([1, 2, 3]), lines([4, 5, 6]), lines([7, 8, 9])];
+        let schedule = "0,1;1,2;0,2".parse().unwrap();
+        let out = xor_128(&[&sources[0], &sources[1], &sources[2]], Some(&schedule));
+        assert_eq!(out, lines([1 ^ 4, 5 ^ 8, 3 ^ 9]));
+        let recovered = xor_128(&[&sources[0], &out, &sources[2]], Some(&schedule));
+        // the lines whose group had source 1 come back as its lines
+        assert_eq!(recovered, lines([4, 5, 3 ^ 9]));
+        // the last group never had it, so its line stays the output's and not source 1's
+        assert_eq!(schedule.without(1), [2]);
+        let last = |text: &str| text.lines().nth(2).unwrap().to_owned();
+        assert_eq!(last(&recovered), last(&out));
+        assert_ne!(last(&recovered), last(&sources[1]));
+        let recovered = xor_128(&[&out, &sources[1], &sources[2]], Some(&schedule));
+        assert_eq!(recovered, lines([1, 5 ^ 8, 3]));
+        assert_eq!(recovered.lines().nth(1), out.lines().nth(1));
+        assert_ne!(recovered.lines().nth(1), sources[0].lines().nth(1));
+    }
+
+    #[test]
+    fn split_a_trickling_pipe_into_a_stream() {
+        let style = Style {
+            bits: 128,
+            ..Style::default()
+        };
+        let secret = [ONES, ZEROS, ONES].concat();
+        let mut source = BufReader::with_capacity(5, Trickle(secret.as_bytes()));
+        let path = scratch("interleaved");
+        split_to_stream(Some(&path), 3, Output::default(), |shares| {
+            let lines = split_inner::<16>(
+                &mut source,
+                shares,
+                Range::default(),
+                &style,
+                None,
+                None,
+                None,
+                0,
+            );
+            assert_eq!(lines, 3);
+        });
+        let mut inputs = interleave::deinterleave(&path, 3);
+        std::fs::remove_file(&path).unwrap();
+        let mut out = Vec::new();
+        xor_inner::<16>(
+            &mut out,
+            &mut inputs,
+            Range::default(),
+            &style,
+            false,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(String::from_utf8(out).unwrap(), secret);
+    }
+
+    #[test]
+    fn pipe_closing_mid_line_fails() {
+        let style = Style {
+            bits: 128,
+            ..Style::default()
+        };
+        let cut = [ONES, &ZEROS[..20]].concat();
+        let mut source = BufReader::with_capacity(5, Trickle(cut.as_bytes()));
+        let err = failure(|| {
+            split_inner::<16>(
+                &mut source,
+                &mut [Vec::new(), Vec::new()],
+                Range::default(),
+                &style,
+                None,
+                None,
+                None,
+                0,
+            );
+        });
+        assert_eq!((err.kind, err.line), ("parse", Some(1)));
+    }
+
+    #[test]
+    fn invalid_utf8_offset_counts_bytes() {
+        let str = &mut String::new();
+        let mut pipe = BufReader::with_capacity(5, Trickle("日本語\n".as_bytes()));
+        assert_eq!(read_text_line(&mut pipe, str, None, 0), 10);
+        assert_eq!(str, "日本語\n");
+        let broken = ["日本".as_bytes(), b"\xff\n"].concat();
+        str.clear();
+        let err = failure(|| {
+            read_text_line(&mut &broken[..], str, Some(2), 4);
+        });
+        assert_eq!(
+            (err.kind, err.file, err.line),
+            ("encoding", Some(2), Some(4))
+        );
+        assert_eq!(err.message, "byte 6 of the line starts invalid UTF-8");
+    }
+
+    #[test]
+    fn bad_first_line_is_line_1() {
+        let typo = ONES.replace("wrong", "wrongg");
+        let err = failure(|| {
+            xor_128(&[ONES, &typo], None);
+        });
+        assert!(
+            err.to_string().starts_with("error on line 1 in file 2: "),
+            "{err}"
+        );
+        let style = Style {
+            bits: 128,
+            ..Style::default()
+        };
+        let err = failure(|| {
+            split_inner::<16>(
+                &mut typo.as_bytes(),
+                &mut [Vec::new(), Vec::new()],
+                Range::default(),
+                &style,
+                None,
+                None,
+                None,
+                0,
+            );
+        });
+        assert!(err.to_string().starts_with("error on line 1: "), "{err}");
+    }
+
+    #[test]
+    fn bom_is_skipped() {
+        let path = scratch("bom");
+        std::fs::write(&path, [BOM, ONES.as_bytes()].concat()).unwrap();
+        let mut input = open_input(&path);
+        std::fs::remove_file(&path).unwrap();
+        let str = &mut String::new();
+        read_data_line(&mut input, str, Some(0), 0);
+        assert_eq!(str, ONES);
+        let style = Style {
+            bits: 128,
+            ..Style::default()
+        };
+        assert_eq!(style.decode(str, 1).unwrap()[..], [0xff; 16]);
+    }
+
+    #[test]
+    fn fold_keeps_indexes_and_cleans_up() {
+        let style = Style {
+            bits: 128,
+            index: true,
+            ..Style::default()
+        };
+        let paths: Vec<_> = (0..3).map(|k| scratch(&format!("fold{k}"))).collect();
+        for (k, path) in (0u8..).zip(&paths) {
+            let mut lines = Vec::new();
+            for (n, byte) in (1..=5).zip(k * 5..) {
+                style.write(&mut lines, n, &[byte; 16]).unwrap();
+            }
+            std::fs::write(path, lines).unwrap();
+        }
+        let range = Range {
+            from_line: 3,
+            ..Range::default()
+        };
+        let dest = scratch("folded");
+        xor_fold(
+            &paths,
+            Some(&dest),
+            range,
+            &style,
+            Output::default(),
+            false,
+            None,
+        );
+        let mut expected = Vec::new();
+        for (n, bytes) in (1..).zip([[2, 7, 12], [3, 8, 13], [4, 9, 14]]) {
+            style
+                .write(&mut expected, n, &[bytes[0] ^ bytes[1] ^ bytes[2]; 16])
+                .unwrap();
+        }
+        assert_eq!(std::fs::read(&dest).unwrap(), expected);
+        std::fs::remove_file(&dest).unwrap();
+        // a last source that ends early fails the last pass, after the first left a temporary
+        let short = std::fs::read_to_string(&paths[2]).unwrap();
+        let short: String = short.split_inclusive('\n').take(3).collect();
+        std::fs::write(&paths[2], short).unwrap();
+        let err = failure(|| {
+            xor_fold(
+                &paths,
+                Some(&dest),
+                range,
+                &style,
+                Output::default(),
+                false,
+                None,
+            );
+        });
+        assert_eq!((err.kind, err.file), ("length", Some(1)));
+        let temporary = format!(".xoriaz-fold-{}-", std::process::id());
+        let left = std::fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .any(|entry| {
+                entry
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&temporary)
+            });
+        assert!(!left, "a temporary file of the fold was left behind");
+        for path in paths.iter().chain([&dest]) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
 }
@@ -0,0 +1,27 @@
+//! `--mlock`, keeping secrets out of swap
+//!
+//! Rather than each buffer a secret passes through, from file buffers to parsed lines,
+//! all memory of the process is locked, now and whatever gets allocated later.
+
+use crate::error::Failure;
+
+/// Lock all memory of the process into RAM, or fail if that's not allowed
+pub fn lock_all() {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: only changes how the kernel pages this process
+        if unsafe { libc::mlockall(libc::MCL_CURRENT | libc::MCL_FUTURE) } != 0 {
+            let err = std::io::Error::last_os_error();
+            Failure::new(
+                "mlock",
+                format_args!(
+                    "couldn't lock memory: {err}; it takes more `ulimit -l` or CAP_IPC_LOCK"
+                ),
+            )
+            .raise();
+        }
+        crate::log::info(format_args!("locked all memory"));
+    }
+    #[cfg(not(target_os = "linux"))]
+    Failure::new("mlock", "--mlock only works on Linux so far").raise();
+}
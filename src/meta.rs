@@ -0,0 +1,279 @@
+//! Sidecar `<file>.meta` files, describing how the lines of a share file are written
+//!
+//! They use the same TOML subset as `--config`, so the share files themselves
+//! stay nothing but lines. A `not-before` date from `split --not-before` is only policy:
+//! `xor` and `check` warn about a share used early, or refuse it with `--respect-time-lock`.
+
+use clap::ArgEnum;
+
+use std::{
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config, create_file,
+    date::{self, Date},
+    error::Failure,
+    finish_file,
+    format::{self, Case, Endian, Format, Separator, Style},
+    log,
+    mac::Algorithm,
+    os_path, Output, Range,
+};
+
+/// Everything a `.meta` file records
+#[derive(Debug, PartialEq, Eq)]
+pub struct Meta {
+    pub style: Style,
+    pub lines: usize,
+    /// The first day the share is meant to be used on, which can differ between shares
+    pub not_before: Option<Date>,
+    /// The `--checksum-algorithm` of its `mac:` line and of the checksum share, if it has any
+    pub checksum: Option<Algorithm>,
+}
+
+/// Where the `.meta` of `file` goes
+pub fn path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".meta");
+    name.into()
+}
+
+/// Describe `file`, which has `lines` lines written in `style` and is meant for `not_before` on
+pub fn write(
+    file: &Path,
+    style: &Style,
+    lines: usize,
+    not_before: Option<Date>,
+    checksum: Option<Algorithm>,
+    output: Output,
+) {
+    let name =
+        |v: Option<clap::PossibleValue>| v.expect("no skipped variants").get_name().to_owned();
+    let text = format!(
+        "format = \"{}\"\nlanguage = \"{}\"\nnumbered = {}\nalign = {}\nhrp = \"{}\"\nendian = \"{}\"\nentropy-hex-uppercase = {}\nmnemonic-case = \"{}\"\nword-separator = \"{}\"\nindex = {}\nwidth = {}\nlines = {lines}\n",
+        name(style.format.to_possible_value()),
+        format::language_name(style.language),
+        style.numbered,
+        style.align,
+        style.hrp,
+        name(style.endian.to_possible_value()),
+        style.entropy_hex_uppercase,
+        name(style.mnemonic_case.to_possible_value()),
+        name(style.word_separator.to_possible_value()),
+        style.index,
+        style.bits,
+    );
+    let text = match not_before {
+        Some(date) => format!("{text}not-before = \"{date}\"\n"),
+        None => text,
+    };
+    let text = match checksum {
+        Some(algorithm) => format!(
+            "{text}checksum-algorithm = \"{}\"\n",
+            name(algorithm.to_possible_value())
+        ),
+        None => text,
+    };
+    let mut w = create_file(&path(file), output);
+    w.write_all(text.as_bytes())
+        .unwrap_or_else(|err| panic!("couldn't write: {err}"));
+    finish_file(w);
+}
+
+/// The `.meta` of a file, and where the file is
+#[derive(Debug)]
+pub struct Sidecar {
+    /// 0-based index of the file among the sources
+    pub file: usize,
+    pub path: PathBuf,
+    pub meta: Meta,
+}
+
+/// The `.meta` of `file`, if it has one
+pub fn read(file: &Path) -> Option<Meta> {
+    let meta_path = path(file);
+    let text = match std::fs::read_to_string(os_path(&meta_path)) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(err) => panic!("couldn't read {}: {err}", meta_path.display()),
+    };
+    Some(parse(&text, &meta_path))
+}
+
+/// The sidecars of those of `sources` that have one
+pub fn of(sources: &[PathBuf]) -> Vec<Sidecar> {
+    let files = sources.iter().enumerate();
+    files
+        .filter(|(_, path)| path.as_os_str() != "-")
+        .filter_map(|(file, path)| {
+            read(path).map(|meta| Sidecar {
+                file,
+                path: path.clone(),
+                meta,
+            })
+        })
+        .collect()
+}
+
+/// The `.meta` in `text`, read from `meta_path`
+pub fn parse(text: &str, meta_path: &Path) -> Meta {
+    let mut style = Style::default();
+    let (mut lines, mut not_before, mut checksum) = (None, None, None);
+    for (n, line) in text.lines().enumerate() {
+        let fail = |why: &dyn Display| -> ! { panic!("{}:{}: {why}", meta_path.display(), n + 1) };
+        let line = config::strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, value) = config::parse_line(line).unwrap_or_else(|why| fail(&why));
+        match key {
+            "format" => {
+                style.format = Format::from_str(&value, false).unwrap_or_else(|why| fail(&why));
+            }
+            "language" => {
+                style.language = format::parse_language(&value).unwrap_or_else(|why| fail(&why));
+            }
+            "numbered" => style.numbered = value == "true",
+            "align" => style.align = value == "true",
+            "index" => style.index = value == "true",
+            "hrp" => style.hrp = value,
+            "endian" => {
+                style.endian = Endian::from_str(&value, false).unwrap_or_else(|why| fail(&why));
+            }
+            "entropy-hex-uppercase" => style.entropy_hex_uppercase = value == "true",
+            "mnemonic-case" => {
+                style.mnemonic_case =
+                    Case::from_str(&value, false).unwrap_or_else(|why| fail(&why));
+            }
+            "word-separator" => {
+                style.word_separator =
+                    Separator::from_str(&value, false).unwrap_or_else(|why| fail(&why));
+            }
+            "width" => {
+                style.bits = match value.parse() {
+                    Ok(bits @ (128 | 192 | 256)) => bits,
+                    _ => fail(&format_args!("{value} isn't one of the widths of --bits")),
+                };
+            }
+            "lines" => lines = Some(value.parse().unwrap_or_else(|why| fail(&why))),
+            "not-before" => not_before = Some(date::parse(&value).unwrap_or_else(|why| fail(&why))),
+            "checksum-algorithm" => {
+                checksum =
+                    Some(Algorithm::from_str(&value, false).unwrap_or_else(|why| fail(&why)));
+            }
+            _ => fail(&format_args!("unknown key `{key}`")),
+        }
+    }
+    let lines =
+        lines.unwrap_or_else(|| panic!("{}: doesn't say how many lines", meta_path.display()));
+    Meta {
+        style,
+        lines,
+        not_before,
+        checksum,
+    }
+}
+
+/// Take the style of `sources` from their `.meta` files, and make `range` expect their length
+///
+/// Options given explicitly have to agree with them
+pub fn configure(sources: &[PathBuf], style: Style, range: &mut Range) -> Style {
+    configure_with(&of(sources), style, range)
+}
+
+/// [`configure`] from sidecars that are already read
+pub fn configure_with(sidecars: &[Sidecar], style: Style, range: &mut Range) -> Style {
+    let Some((first, rest)) = sidecars.split_first() else {
+        return style;
+    };
+    for other in rest {
+        assert!(
+            (&first.meta.style, first.meta.lines) == (&other.meta.style, other.meta.lines),
+            "{} and {} disagree",
+            path(&first.path).display(),
+            path(&other.path).display()
+        );
+    }
+    let (source, meta) = (&first.path, &first.meta);
+    // how strictly to read isn't a property of the file
+    let strict_whitespace = style.strict_whitespace;
+    let style = Style {
+        strict_whitespace: false,
+        ..style
+    };
+    assert!(
+        style == Style::default() || style == meta.style,
+        "the options given disagree with {}",
+        path(source).display()
+    );
+    if range.is_everything() && range.expect_lines.is_none() {
+        range.expect_lines = Some(meta.lines);
+    }
+    Style {
+        strict_whitespace,
+        ..meta.style.clone()
+    }
+}
+
+/// The `--checksum-algorithm` the `sidecars` record, in place of the default `given`
+///
+/// One given explicitly has to agree with them
+pub fn checksum_algorithm(sidecars: &[Sidecar], given: Algorithm) -> Algorithm {
+    let mut found: Option<(&Path, Algorithm)> = None;
+    for sidecar in sidecars {
+        let Some(algorithm) = sidecar.meta.checksum else {
+            continue;
+        };
+        match found {
+            Some((first, seen)) => assert!(
+                seen == algorithm,
+                "{} and {} disagree on the checksum algorithm",
+                path(first).display(),
+                path(&sidecar.path).display()
+            ),
+            None => found = Some((&sidecar.path, algorithm)),
+        }
+    }
+    let Some((source, recorded)) = found else {
+        return given;
+    };
+    assert!(
+        given == Algorithm::Sha256 || given == recorded,
+        "--checksum-algorithm disagrees with {}",
+        path(source).display()
+    );
+    recorded
+}
+
+/// Warn about any of `sidecars` that says its file is not to be used yet, or refuse it if `respect`
+pub fn check_time_lock(sidecars: &[Sidecar], respect: bool) {
+    let today = Date::today();
+    for Sidecar {
+        file: f,
+        path: source,
+        meta,
+    } in sidecars
+    {
+        let Some(date) = meta.not_before else {
+            continue;
+        };
+        if date <= today {
+            continue;
+        }
+        if respect {
+            Failure::new(
+                "time-lock",
+                format_args!("{} isn't to be used before {date}", source.display()),
+            )
+            .file(*f)
+            .raise();
+        }
+        log::warn(format_args!(
+            "{} isn't meant to be used before {date}, --respect-time-lock would refuse it",
+            source.display()
+        ));
+    }
+}
@@ -0,0 +1,379 @@
+//! Parity files from `split --parity`, and `recover` to put lost shares back together from them
+//!
+//! Share `i` of `n` is `d_i`, and parity file `j` of `P` is the sum of `a_ji * d_i`, byte by byte in GF(2^8).
+//! The `a_ji` are a Cauchy matrix with its columns scaled so that the sum with all ones,
+//! the secret, is one more row of the same code. Any `n` rows of it are independent, so
+//! any `n` of the `n + P` files give back all the others, while any `n - 1` of them
+//! still say nothing about the secret, same as `n - 1` shares without parity.
+
+use clap::Args;
+
+use std::{iter::zip, path::PathBuf};
+
+use crate::{
+    check_width, create_file,
+    error::Failure,
+    finish_file,
+    format::{Encoding, Entropy, Style},
+    log, meta, open_input, read_data_line, report, Input, Output, Range,
+};
+
+#[derive(Debug, Args)]
+pub struct Recover {
+    /// Every share of the split in order, where the ones that don't exist anymore get written back
+    #[clap(required = true, min_values = 2, parse(from_os_str))]
+    shares: Vec<PathBuf>,
+    /// The parity files of the split, in the order `split --parity` got them
+    ///
+    /// Missing ones get written back too, and any left over after recovering are checked
+    #[clap(long, value_name = "FILE", required = true, parse(from_os_str))]
+    parity: Vec<PathBuf>,
+    #[clap(flatten)]
+    style: Style,
+    #[clap(flatten)]
+    output: Output,
+}
+
+/// Write the `parity` files of the finished `shares`, written in `style`
+pub fn write(shares: &[PathBuf], parity: &[PathBuf], style: &Style, output: Output) {
+    let a = coefficients(shares.len(), parity.len());
+    let mut files: Vec<_> = shares
+        .iter()
+        .enumerate()
+        .map(|(f, path)| (f, open_input(path)))
+        .collect();
+    let mut writers: Vec<_> = parity
+        .iter()
+        .map(|path| create_file(path, output))
+        .collect();
+    let mut i = 0;
+    while let Some(lines) = read_lines(&mut files, style, i) {
+        for (row, w) in zip(&a, &mut writers) {
+            style
+                .write(w, i + 1, &combine(row, &lines))
+                .unwrap_or_else(|err| panic!("couldn't write: {err}"));
+        }
+        i += 1;
+    }
+    for w in writers {
+        finish_file(w);
+    }
+    log::info(format_args!("wrote {} parity files", parity.len()));
+}
+
+pub fn run(
+    Recover {
+        shares,
+        parity,
+        style,
+        output,
+    }: Recover,
+) {
+    let count = shares.len();
+    let all: Vec<_> = shares.iter().chain(&parity).cloned().collect();
+    assert!(
+        all.iter().all(|path| path.as_os_str() != "-"),
+        "recover needs the files themselves, not standard input"
+    );
+    let (present, missing): (Vec<_>, Vec<_>) = (0..all.len()).partition(|&f| all[f].exists());
+    let lost: Vec<_> = missing.iter().copied().filter(|&f| f < count).collect();
+    let left: Vec<_> = present
+        .iter()
+        .filter(|&&f| f >= count)
+        .map(|&f| f - count)
+        .collect();
+    if lost.len() > left.len() {
+        Failure::new(
+            "parity",
+            format_args!(
+                "{} shares are missing, but only {} parity files are left to recover them",
+                lost.len(),
+                left.len()
+            ),
+        )
+        .raise();
+    }
+    let present_paths: Vec<_> = present.iter().map(|&f| all[f].clone()).collect();
+    let style = meta::configure(&present_paths, style, &mut Range::default());
+    report::style(&style);
+    let rows = coefficients(count, parity.len());
+    let (used, checked) = left.split_at(lost.len());
+    let solve = invert(
+        used.iter()
+            .map(|&j| lost.iter().map(|&i| rows[j][i]).collect())
+            .collect(),
+    );
+    let mut files: Vec<_> = present.iter().map(|&f| (f, open_input(&all[f]))).collect();
+    let mut writers: Vec<_> = missing
+        .iter()
+        .map(|&f| (f, create_file(&all[f], output)))
+        .collect();
+    let (mut i, mut mismatched) = (0, 0);
+    while let Some(lines) = read_lines(&mut files, &style, i) {
+        let mut share_lines: Vec<Entropy> = vec![Entropy::from_elem(0, style.bytes()); count];
+        let mut parity_lines: Vec<Option<Entropy>> = vec![None; parity.len()];
+        for (&f, line) in zip(&present, lines) {
+            if f < count {
+                share_lines[f] = line;
+            } else {
+                parity_lines[f - count] = Some(line);
+            }
+        }
+        // what's left of each used parity line once the shares that are there are taken out
+        let rest: Vec<_> = used
+            .iter()
+            .map(|&j| {
+                let mut rest = parity_lines[j].clone().expect("present");
+                for (x, y) in zip(&mut rest, combine(&rows[j], &share_lines)) {
+                    *x ^= y;
+                }
+                rest
+            })
+            .collect();
+        for (row, &share) in zip(&solve, &lost) {
+            share_lines[share] = combine(row, &rest);
+        }
+        for &j in checked {
+            if combine(&rows[j], &share_lines) != parity_lines[j].clone().expect("present") {
+                eprintln!("line {} doesn't match {}", i + 1, parity[j].display());
+                mismatched += 1;
+            }
+        }
+        for (f, w) in &mut writers {
+            let line = if *f < count {
+                share_lines[*f].clone()
+            } else {
+                combine(&rows[*f - count], &share_lines)
+            };
+            style
+                .write(w, i + 1, &line)
+                .unwrap_or_else(|err| panic!("couldn't write: {err}"));
+        }
+        i += 1;
+    }
+    for (_, w) in writers {
+        finish_file(w);
+    }
+    report::lines(i);
+    summarize(
+        lost.len(),
+        missing.len() - lost.len(),
+        checked.len(),
+        mismatched,
+    );
+}
+
+/// Say what `recover` did, and fail if any of the `checked` parity files had `mismatched` lines
+fn summarize(shares: usize, parity: usize, checked: usize, mismatched: usize) {
+    eprintln!("wrote back {shares} shares and {parity} parity files");
+    if checked == 0 {
+        log::warn(format_args!(
+            "no parity files were left over to check the shares against"
+        ));
+    } else if mismatched != 0 {
+        Failure::new(
+            "parity",
+            format_args!("{mismatched} lines don't match the parity files"),
+        )
+        .raise();
+    } else {
+        eprintln!("every line matches the {checked} parity files left over");
+    }
+}
+
+/// Line `i` of each of `files`, tagged with their index, or nothing once they've all ended
+fn read_lines(files: &mut [(usize, Input)], style: &Style, i: usize) -> Option<Vec<Entropy>> {
+    let str = &mut String::new();
+    let mut lines = Vec::with_capacity(files.len());
+    for (f, file) in files.iter_mut() {
+        str.clear();
+        if read_data_line(file, str, Some(*f), i) == 0 {
+            continue;
+        }
+        let entropy = match style.decode(str, i + 1) {
+            Ok(entropy) => entropy,
+            Err(e) => Failure::new("parse", e).file(*f).line(i).raise(),
+        };
+        check_width(&entropy, style.bytes(), Some(*f), i);
+        lines.push(entropy);
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    if lines.len() != files.len() {
+        Failure::new("length", "some of the files end before the others")
+            .line(i)
+            .raise();
+    }
+    Some(lines)
+}
+
+/// The sum of `row[k] * lines[k]`, byte by byte
+fn combine(row: &[u8], lines: &[Entropy]) -> Entropy {
+    let mut sum = Entropy::from_elem(0, lines.first().map_or(0, Entropy::len));
+    for (&c, line) in zip(row, lines) {
+        for (s, &b) in zip(&mut sum, line) {
+            *s ^= mul(c, b);
+        }
+    }
+    sum
+}
+
+/// The `parity` rows of `a_ji` for `shares` shares
+fn coefficients(shares: usize, parity: usize) -> Vec<Vec<u8>> {
+    assert!(
+        shares + parity < 256,
+        "at most 255 shares and parity files together"
+    );
+    let point = |k: usize| u8::try_from(k).expect("checked");
+    // the Cauchy matrix 1 / (x_r + y_i) with x_r = r and y_i = parity + 1 + i,
+    // each column divided by its row 0, which becomes the row of the secret
+    (1..=parity)
+        .map(|r| {
+            (0..shares)
+                .map(|i| {
+                    let y = point(parity + 1 + i);
+                    mul(inv(point(r) ^ y), y)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Multiply in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1, without branching on the bytes of shares
+const fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    let mut bit = 0;
+    while bit < 8 {
+        product ^= a & (b & 1).wrapping_neg();
+        a = (a << 1) ^ (0x1d & (a >> 7).wrapping_neg());
+        b >>= 1;
+        bit += 1;
+    }
+    product
+}
+
+/// The inverse of a nonzero `a`, which is `a^254`
+const fn inv(a: u8) -> u8 {
+    let mut power = 1;
+    let mut k = 0;
+    while k < 254 {
+        power = mul(power, a);
+        k += 1;
+    }
+    power
+}
+
+/// The inverse of the square matrix `m`, which any square part of a Cauchy matrix has
+fn invert(mut m: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+    let size = m.len();
+    let mut inverse: Vec<Vec<u8>> = (0..size)
+        .map(|r| (0..size).map(|c| u8::from(r == c)).collect())
+        .collect();
+    for col in 0..size {
+        let pivot = (col..size)
+            .find(|&r| m[r][col] != 0)
+            .expect("parity coefficients are always invertible");
+        m.swap(col, pivot);
+        inverse.swap(col, pivot);
+        let scale = inv(m[col][col]);
+        for c in 0..size {
+            m[col][c] = mul(m[col][c], scale);
+            inverse[col][c] = mul(inverse[col][c], scale);
+        }
+        for r in 0..size {
+            let factor = m[r][col];
+            if r == col || factor == 0 {
+                continue;
+            }
+            for c in 0..size {
+                m[r][c] ^= mul(factor, m[col][c]);
+                inverse[r][c] ^= mul(factor, inverse[col][c]);
+            }
+        }
+    }
+    inverse
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{failure, scratch};
+
+    /// 4 shares of 3 lines and 2 parity files, in a new directory, with what they hold
+    fn split_4_2(dir: &std::path::Path) -> (Recover, Vec<Vec<u8>>) {
+        std::fs::create_dir(dir).unwrap();
+        let style = Style::default();
+        let shares: Vec<_> = (0..4).map(|k| dir.join(format!("share{k}"))).collect();
+        let parity: Vec<_> = (0..2).map(|j| dir.join(format!("parity{j}"))).collect();
+        for (k, path) in (0u8..).zip(&shares) {
+            let mut lines = Vec::new();
+            for (n, byte) in (1..=3).zip(k * 3..) {
+                style
+                    .write(&mut lines, n, &[byte.wrapping_mul(37); 32])
+                    .unwrap();
+            }
+            std::fs::write(path, lines).unwrap();
+        }
+        write(&shares, &parity, &style, Output::default());
+        let all = shares.iter().chain(&parity);
+        let contents = all.map(|path| std::fs::read(path).unwrap()).collect();
+        let recover = Recover {
+            shares,
+            parity,
+            style,
+            output: Output::default(),
+        };
+        (recover, contents)
+    }
+
+    fn all(recover: &Recover) -> Vec<PathBuf> {
+        recover
+            .shares
+            .iter()
+            .chain(&recover.parity)
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn recovers_every_loss_of_one_or_two() {
+        let dir = scratch("parity");
+        let (recover, contents) = split_4_2(&dir);
+        let paths = all(&recover);
+        let patterns = (0..6).flat_map(|a| (a..6).map(move |b| [a, b]));
+        for lost in patterns {
+            for &f in &lost {
+                let _ = std::fs::remove_file(&paths[f]);
+            }
+            run(Recover {
+                shares: recover.shares.clone(),
+                parity: recover.parity.clone(),
+                style: recover.style.clone(),
+                output: Output::default(),
+            });
+            for (path, expected) in zip(&paths, &contents) {
+                assert_eq!(&std::fs::read(path).unwrap(), expected, "lost {lost:?}");
+            }
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fails_when_too_much_is_lost() {
+        let dir = scratch("parity-lost");
+        let (recover, _) = split_4_2(&dir);
+        let paths = all(&recover);
+        for f in [0, 2, 3] {
+            std::fs::remove_file(&paths[f]).unwrap();
+        }
+        let err = failure(|| run(recover));
+        assert_eq!(err.kind, "parity");
+        assert_eq!(
+            err.message,
+            "3 shares are missing, but only 2 parity files are left to recover them"
+        );
+        assert!(!paths[0].exists(), "nothing gets written");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
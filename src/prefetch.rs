@@ -0,0 +1,83 @@
+//! Sources read ahead by a thread each, so slow storage doesn't stall the XOR loop
+//!
+//! Lines arrive through a bounded channel in order, and decoding still happens
+//! where they're used, so errors point at the same file and line as without it.
+
+use std::{
+    io::{BufRead, Read},
+    path::PathBuf,
+    sync::mpsc::{sync_channel, Receiver},
+    thread::JoinHandle,
+};
+
+use crate::open_input;
+
+/// How many lines each thread may get ahead
+const LINES_AHEAD: usize = 256;
+
+pub struct Prefetch {
+    lines: Receiver<std::io::Result<Vec<u8>>>,
+    line: Vec<u8>,
+    pos: usize,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Prefetch {
+    /// Open `path` and start reading it in the background
+    pub fn spawn(path: PathBuf) -> Self {
+        let (tx, lines) = sync_channel(LINES_AHEAD);
+        let thread = std::thread::spawn(move || {
+            let mut file = open_input(&path);
+            loop {
+                let mut line = Vec::new();
+                match file.read_until(b'\n', &mut line) {
+                    Ok(0) => break,
+                    // nobody's listening anymore
+                    Ok(_) if tx.send(Ok(line)).is_err() => break,
+                    Ok(_) => {}
+                    Err(err) => {
+                        drop(tx.send(Err(err)));
+                        break;
+                    }
+                }
+            }
+        });
+        Self {
+            lines,
+            line: Vec::new(),
+            pos: 0,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Read for Prefetch {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(out.len());
+        out[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for Prefetch {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.pos == self.line.len() {
+            self.pos = 0;
+            if let Ok(line) = self.lines.recv() {
+                self.line = line?;
+            } else {
+                self.line.clear();
+                // the thread hung up, pass on its panic if that's why
+                if let Some(Err(panic)) = self.thread.take().map(JoinHandle::join) {
+                    std::panic::resume_unwind(panic);
+                }
+            }
+        }
+        Ok(&self.line[self.pos..])
+    }
+    fn consume(&mut self, n: usize) {
+        self.pos += n;
+    }
+}
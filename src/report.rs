@@ -0,0 +1,124 @@
+//! `--report-file`, a record of every operation for audit logs
+//!
+//! Each run appends an `[[operation]]` table in the TOML subset of `--config`.
+//! Like the logs it only says which files were read and how much, never what's in them.
+
+use std::{
+    fmt::Write as _,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::Instant,
+};
+
+use crate::{error::json_str, format::Style, utc_timestamp};
+
+struct Report {
+    path: PathBuf,
+    command: String,
+    timestamp: String,
+    started: Instant,
+    inputs: Vec<PathBuf>,
+    lines: Option<usize>,
+    style: Option<Style>,
+}
+
+static REPORT: Mutex<Option<Report>> = Mutex::new(None);
+
+fn with(f: impl FnOnce(&mut Report)) {
+    if let Some(report) = REPORT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_mut()
+    {
+        f(report);
+    }
+}
+
+/// Start recording `command`, to be appended to `path` once it's over
+pub fn start(path: PathBuf, command: &str) {
+    *REPORT.lock().unwrap_or_else(PoisonError::into_inner) = Some(Report {
+        path,
+        command: command.to_owned(),
+        timestamp: utc_timestamp(),
+        started: Instant::now(),
+        inputs: Vec::new(),
+        lines: None,
+        style: None,
+    });
+}
+
+/// Record that `path` was read
+pub fn input(path: &Path) {
+    with(|report| {
+        if !report.inputs.iter().any(|p| p == path) {
+            report.inputs.push(path.to_owned());
+        }
+    });
+}
+
+/// Record how many lines were processed
+pub fn lines(n: usize) {
+    with(|report| report.lines = Some(n));
+}
+
+/// Record how lines were read or written
+pub fn style(style: &Style) {
+    with(|report| report.style = Some(style.clone()));
+}
+
+/// Append the report, if there's one, with the `error` that ended the operation early
+pub fn finish(error: Option<&str>) {
+    let Some(report) = REPORT.lock().unwrap_or_else(PoisonError::into_inner).take() else {
+        return;
+    };
+    let mut text = format!(
+        "[[operation]]\ncommand = {}\ntimestamp = \"{}\"\n",
+        json_str(&report.command),
+        report.timestamp
+    );
+    let inputs: Vec<_> = report
+        .inputs
+        .iter()
+        .map(|p| json_str(&p.to_string_lossy()))
+        .collect();
+    writeln!(text, "inputs = [{}]", inputs.join(", ")).unwrap();
+    if let Some(lines) = report.lines {
+        writeln!(text, "lines = {lines}").unwrap();
+    }
+    if let Some(style) = &report.style {
+        writeln!(
+            text,
+            "width = {}\nlanguage = \"{}\"",
+            style.bits,
+            crate::format::language_name(style.language)
+        )
+        .unwrap();
+    }
+    match error {
+        None => text.push_str("result = \"success\"\n"),
+        Some(error) => writeln!(text, "result = \"failure\"\nerror = {}", json_str(error)).unwrap(),
+    }
+    writeln!(
+        text,
+        "duration = {:.3}\n",
+        report.started.elapsed().as_secs_f64()
+    )
+    .unwrap();
+    let mut opts = std::fs::OpenOptions::new();
+    opts.append(true).create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+    let written = opts
+        .open(&report.path)
+        .and_then(|mut file| file.write_all(text.as_bytes()));
+    if let Err(err) = written {
+        eprintln!("couldn't write report {}: {err}", report.path.display());
+    }
+}
+
+/// Append the report as failed, then exit with `code`
+pub fn exit(code: i32) -> ! {
+    finish(Some(&format!("exited with {code}")));
+    std::process::exit(code)
+}
@@ -0,0 +1,70 @@
+//! `--io-retries`, for shares on network filesystems that fail now and then
+//!
+//! Only errors that can pass on their own are retried, anything like a missing
+//! permission still fails right away.
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    sync::atomic::{AtomicU32, Ordering},
+    thread,
+    time::Duration,
+};
+
+use crate::log;
+
+static RETRIES: AtomicU32 = AtomicU32::new(0);
+
+/// Wait before the first retry, doubled for each further one
+const FIRST_DELAY: Duration = Duration::from_millis(100);
+
+pub fn set(retries: u32) {
+    RETRIES.store(retries, Ordering::Relaxed);
+}
+
+const fn transient(kind: ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::Interrupted
+            | ErrorKind::WouldBlock
+            | ErrorKind::TimedOut
+            | ErrorKind::ResourceBusy
+    )
+}
+
+fn retry<T>(what: &str, mut f: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let retries = RETRIES.load(Ordering::Relaxed);
+    let mut delay = FIRST_DELAY;
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Err(err) if attempt < retries && transient(err.kind()) => {
+                attempt += 1;
+                log::info(format_args!(
+                    "{what} failed with {err}, retry {attempt} of {retries} in {delay:?}"
+                ));
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// A file whose reads and writes are retried on transient errors
+#[derive(Debug)]
+pub struct Retry<T>(pub T);
+
+impl<T: Read> Read for Retry<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        retry("read", || self.0.read(buf))
+    }
+}
+
+impl<T: Write> Write for Retry<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        retry("write", || self.0.write(buf))
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        retry("flush", || self.0.flush())
+    }
+}
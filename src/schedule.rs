@@ -0,0 +1,102 @@
+//! Which sources go into which line of the output, for `xor --schedule`
+//!
+//! A schedule is a list of groups of 0-based source positions, like `0,1;1,2;0,2`.
+//! Output line `i` is the XOR of the lines of group `i % groups` only,
+//! the lines of the other sources are decoded but left out.
+//!
+//! XOR undoes itself, so the inverse is the same schedule with the output in place of
+//! a lost source: every line whose group had that source then comes out as its line again.
+//! That inverse is only partial. The lines of the other groups never had the lost source
+//! in them and come out as they were in the output, so `xor --recover` warns about them.
+
+use std::{fmt, str::FromStr};
+
+use smallvec::SmallVec;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule(Vec<SmallVec<[usize; 4]>>);
+
+impl FromStr for Schedule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        s.split(';')
+            .map(|group| {
+                let group = group
+                    .split(',')
+                    .map(|n| n.trim().parse().map_err(|e| format!("`{n}`: {e}")))
+                    .collect::<Result<SmallVec<_>, _>>()?;
+                Ok(group)
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl fmt::Display for Schedule {
+    /// The way it's written on the command line, which parses back to the same schedule
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (g, group) in self.0.iter().enumerate() {
+            if g != 0 {
+                f.write_str(";")?;
+            }
+            for (n, source) in group.iter().enumerate() {
+                if n != 0 {
+                    f.write_str(",")?;
+                }
+                write!(f, "{source}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Schedule {
+    /// Panic unless every position is one of `sources`
+    pub fn check(&self, sources: usize) {
+        for group in &self.0 {
+            if let Some(&n) = group.iter().find(|&&n| n >= sources) {
+                panic!("--schedule uses source {n}, but there are only {sources}, counting from 0");
+            }
+        }
+    }
+    /// Whether the 0-based `source` goes into the 0-based output `line`
+    pub fn includes(&self, line: usize, source: usize) -> bool {
+        self.0[line % self.0.len()].contains(&source)
+    }
+    /// The 0-based groups that leave out the 0-based `source`
+    pub fn without(&self, source: usize) -> Vec<usize> {
+        let groups = self.0.iter().enumerate();
+        groups
+            .filter(|(_, group)| !group.contains(&source))
+            .map(|(g, _)| g)
+            .collect()
+    }
+    /// How many different sources go into the 0-based output `line`
+    pub fn sources(&self, line: usize) -> usize {
+        let group = &self.0[line % self.0.len()];
+        let distinct = group.iter().enumerate();
+        distinct.filter(|&(k, n)| !group[..k].contains(n)).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn survives_writing_and_parsing() {
+        let schedule: Schedule = "0,1; 1,2 ;0, 2,2".parse().unwrap();
+        let written = schedule.to_string();
+        assert_eq!(written, "0,1;1,2;0,2,2");
+        assert_eq!(written.parse(), Ok(schedule.clone()));
+        assert_eq!(
+            (0..4)
+                .map(|line| schedule.sources(line))
+                .collect::<Vec<_>>(),
+            [2, 2, 2, 2]
+        );
+        assert!(schedule.includes(3, 1) && !schedule.includes(3, 2));
+        assert_eq!(schedule.without(1), [2]);
+        assert_eq!(schedule.without(0), [1]);
+    }
+}
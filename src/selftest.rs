@@ -0,0 +1,79 @@
+//! `selftest`, splitting and recombining generated secrets in every format and width,
+//! and checking the built-in wordlists against BIP39
+//!
+//! Everything happens in memory, so a failure points at the build rather than at any file.
+
+use clap::ArgEnum;
+
+use crate::{
+    format::{Format, Style},
+    gen_inner, split_inner, xor_inner, Range,
+};
+
+const LINES: usize = 20;
+
+pub fn run() {
+    let mut failed = false;
+    for &format in Format::value_variants() {
+        for bits in [128, 192, 256] {
+            for shares in [2, 3, 5] {
+                let style = Style {
+                    format,
+                    bits,
+                    ..Style::default()
+                };
+                let ok = with_width!(style, N => round_trip::<N>(shares, &style));
+                println!(
+                    "{} {bits}-bit {format:?} split into {shares} and recombined",
+                    if ok { "PASS" } else { "FAIL" }
+                );
+                failed |= !ok;
+            }
+        }
+    }
+    for &language in bip39::Language::all() {
+        let ok = crate::wordlist::matches(language);
+        println!(
+            "{} {} wordlist is the BIP39 one",
+            if ok { "PASS" } else { "FAIL" },
+            crate::format::language_name(language)
+        );
+        failed |= !ok;
+    }
+    if failed {
+        crate::report::exit(1);
+    }
+}
+
+fn round_trip<const N: usize>(shares: usize, style: &Style) -> bool {
+    let mut secret = Vec::new();
+    gen_inner::<N>(&mut secret, LINES, style, None, 0);
+    let mut dests = vec![Vec::new(); shares];
+    split_inner::<N>(
+        &mut &secret[..],
+        &mut dests,
+        Range::default(),
+        style,
+        None,
+        None,
+        None,
+        0,
+    );
+    if dests.iter().any(|share| share == &secret) {
+        return false;
+    }
+    let mut inputs: Vec<&[u8]> = dests.iter().map(Vec::as_slice).collect();
+    let mut combined = Vec::new();
+    xor_inner::<N>(
+        &mut combined,
+        &mut inputs,
+        Range::default(),
+        style,
+        false,
+        None,
+        None,
+        None,
+        None,
+    );
+    combined == secret
+}
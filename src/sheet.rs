@@ -0,0 +1,189 @@
+//! `split --sheet`, a printable PDF of each share to hand to whoever keeps it
+//!
+//! The PDF is put together by hand, in the Courier and Helvetica every reader has built in,
+//! so there's only room for Latin letters. Nothing here can draw a QR code.
+
+use bip39::Mnemonic;
+use unicode_normalization::UnicodeNormalization;
+
+use std::{
+    fmt::Write as _,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    check_width, create_file,
+    error::Failure,
+    finish_file,
+    format::{Encoding, Style},
+    open_input, read_data_line, Output,
+};
+
+/// A4, in points
+const PAGE_WIDTH: usize = 595;
+const PAGE_HEIGHT: usize = 842;
+const MARGIN: usize = 56;
+/// Distance between the baselines of the 10pt lines
+const LEADING: usize = 13;
+const WORDS_PER_ROW: usize = 6;
+/// Courier is 0.6em wide, so this fits `24:mnemonic` and some space
+const COLUMN: usize = 78;
+
+/// `sheet` with the number `k` of the share before the extension, like `out_1.pdf`
+pub fn name(sheet: &Path, k: usize) -> PathBuf {
+    let mut name = sheet.file_stem().unwrap_or_default().to_owned();
+    name.push(format!("_{k}"));
+    if let Some(ext) = sheet.extension() {
+        name.push(".");
+        name.push(ext);
+    }
+    sheet.with_file_name(name)
+}
+
+/// Write share `k` of `n` at `share`, written in `style`, as numbered words into a new PDF `sheet`
+pub fn write(share: &Path, k: usize, n: usize, sheet: &Path, style: &Style, output: Output) {
+    let label = share.file_name().map_or_else(
+        || share.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let mut pages = Vec::new();
+    let mut file = open_input(share);
+    let str = &mut String::new();
+    let mut i = 0;
+    while read_data_line(&mut file, str, None, i) != 0 {
+        let entropy = match style.decode(str, i + 1) {
+            Ok(entropy) => entropy,
+            Err(e) => Failure::new("parse", e).line(i).raise(),
+        };
+        str.clear();
+        check_width(&entropy, style.bytes(), None, i);
+        let mnemonic = Mnemonic::from_entropy_in(style.language, &entropy).expect("checked width");
+        let words: Vec<_> = mnemonic.word_iter().collect();
+        let rows = words.len().div_ceil(WORDS_PER_ROW);
+        if pages.last().is_none_or(|page: &Page| !page.fits(rows + 1)) {
+            pages.push(Page::new(&format!("share {k} of {n}"), &label));
+        }
+        let page = pages.last_mut().expect("just pushed");
+        page.line("F2", &format!("line {}", i + 1));
+        for (row, chunk) in words.chunks(WORDS_PER_ROW).enumerate() {
+            page.y -= LEADING;
+            for (col, word) in chunk.iter().enumerate() {
+                let number = row * WORDS_PER_ROW + col + 1;
+                page.text("F1", MARGIN + col * COLUMN, &format!("{number}:{word}"));
+            }
+        }
+        page.y -= LEADING * 2;
+        i += 1;
+    }
+    if pages.is_empty() {
+        pages.push(Page::new(&format!("share {k} of {n}"), &label));
+    }
+    let mut pdf = assemble(&mut pages);
+    let mut w = create_file(sheet, output);
+    w.write_all(&pdf)
+        .unwrap_or_else(|err| panic!("couldn't write {}: {err}", sheet.display()));
+    finish_file(w);
+    pdf.fill(0);
+}
+
+/// The content stream of a page being filled from the top
+struct Page {
+    content: Vec<u8>,
+    /// Baseline of the last line
+    y: usize,
+}
+
+impl Page {
+    fn new(header: &str, label: &str) -> Self {
+        let mut page = Self {
+            content: Vec::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        };
+        page.show("F2", 16, MARGIN, page.y, header);
+        page.y -= LEADING * 2;
+        page.line("F1", label);
+        page.y -= LEADING * 2;
+        page
+    }
+    /// Whether `lines` more lines go above the bottom margin
+    const fn fits(&self, lines: usize) -> bool {
+        self.y >= MARGIN + lines * LEADING
+    }
+    /// A 10pt line of `text` on the next baseline
+    fn line(&mut self, font: &str, text: &str) {
+        self.text(font, MARGIN, text);
+    }
+    /// 10pt `text` at `x` on the current baseline
+    fn text(&mut self, font: &str, x: usize, text: &str) {
+        self.show(font, 10, x, self.y, text);
+    }
+    fn show(&mut self, font: &str, size: usize, x: usize, y: usize, text: &str) {
+        write!(self.content, "BT /{font} {size} Tf {x} {y} Td (").expect("in memory");
+        // the wordlists are decomposed, but WinAnsi only has the composed accented letters
+        for c in text.nfc() {
+            let Ok(b) = u8::try_from(u32::from(c)) else {
+                panic!("--sheet can only print Latin letters, not `{c}`, pick another --language");
+            };
+            match b {
+                b'(' | b')' | b'\\' => self.content.extend([b'\\', b]),
+                _ => self.content.push(b),
+            }
+        }
+        self.content.extend_from_slice(b") Tj ET\n");
+    }
+}
+
+/// A whole PDF of `pages`, whose content streams are zeroed again as they're copied in
+fn assemble(pages: &mut [Page]) -> Vec<u8> {
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    let mut object = |pdf: &mut Vec<u8>, body: &[u8]| {
+        offsets.push(pdf.len());
+        writeln!(pdf, "{} 0 obj", offsets.len()).expect("in memory");
+        pdf.extend_from_slice(body);
+        pdf.extend_from_slice(b"\nendobj\n");
+    };
+    object(&mut pdf, b"<< /Type /Catalog /Pages 2 0 R >>");
+    let mut kids = String::new();
+    for p in 0..pages.len() {
+        write!(kids, "{} 0 R ", 5 + 2 * p).expect("in memory");
+    }
+    let pages_object = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] >>",
+        kids.trim_end(),
+        pages.len()
+    );
+    object(&mut pdf, pages_object.as_bytes());
+    for font in ["Courier", "Helvetica-Bold"] {
+        let body = format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{font} /Encoding /WinAnsiEncoding >>"
+        );
+        object(&mut pdf, body.as_bytes());
+    }
+    for (p, page) in pages.iter_mut().enumerate() {
+        let body = format!(
+            "<< /Type /Page /Parent 2 0 R /Contents {} 0 R /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> >>",
+            6 + 2 * p
+        );
+        object(&mut pdf, body.as_bytes());
+        let mut stream = format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+        stream.extend_from_slice(&page.content);
+        stream.extend_from_slice(b"\nendstream");
+        object(&mut pdf, &stream);
+        stream.fill(0);
+        page.content.fill(0);
+    }
+    let xref = pdf.len();
+    write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).expect("in memory");
+    for offset in &offsets {
+        writeln!(pdf, "{offset:010} 00000 n ").expect("in memory");
+    }
+    write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        offsets.len() + 1
+    )
+    .expect("in memory");
+    pdf
+}
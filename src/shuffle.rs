@@ -0,0 +1,105 @@
+//! `shuffle`, reordering the lines of a file by a seeded permutation
+//!
+//! The same seed and line count always give the same order, so `--inverse` can put the lines
+//! back, and `--permutation` records where each output line came from for realigning shares.
+//! It stays apart from `xor` and `split`, which never reorder anything themselves.
+
+use clap::Args;
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+use std::{
+    io::{stdout, BufRead, Write},
+    path::PathBuf,
+};
+
+use crate::{create_file, finish_file, open_input, parse_size, Output};
+
+#[derive(Debug, Args)]
+pub struct Shuffle {
+    #[clap(parse(from_os_str))]
+    source: PathBuf,
+    #[clap(short = 'o', long = "out", parse(from_os_str))]
+    dest: Option<PathBuf>,
+    /// Seed of the permutation, the same seed and line count always give the same order
+    #[clap(long)]
+    seed: u64,
+    /// Undo a shuffle that was made with the same seed
+    #[clap(long)]
+    inverse: bool,
+    /// Also write the permutation, as the original 0-based line number of each output line
+    #[clap(long, parse(from_os_str))]
+    permutation: Option<PathBuf>,
+    /// Leave out blank lines at the end of the source, instead of shuffling them in
+    #[clap(long)]
+    trim_trailing_empty: bool,
+    /// Give up instead of holding more than this much of the source in memory, like `512M`
+    ///
+    /// Shuffling has to read all of it first
+    #[clap(long, value_name = "SIZE", parse(try_from_str = parse_size))]
+    max_memory: Option<u64>,
+    #[clap(flatten)]
+    output: Output,
+}
+
+pub fn run(
+    Shuffle {
+        source,
+        dest,
+        seed,
+        inverse,
+        permutation,
+        trim_trailing_empty,
+        max_memory,
+        output,
+    }: Shuffle,
+) {
+    let mut lines = Vec::new();
+    let mut held = 0;
+    for line in open_input(&source).lines() {
+        let line = line.expect("couldn't read");
+        held += (line.len() + std::mem::size_of::<String>()) as u64;
+        if let Some(max) = max_memory.filter(|&max| held > max) {
+            panic!(
+                "shuffling {} takes more than --max-memory {max} bytes",
+                source.display()
+            );
+        }
+        lines.push(line);
+    }
+    while trim_trailing_empty && lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+    let mut perm: Vec<usize> = (0..lines.len()).collect();
+    perm.shuffle(&mut ChaCha20Rng::seed_from_u64(seed));
+    let order = if inverse {
+        let mut inv = vec![0; perm.len()];
+        for (to, &from) in perm.iter().enumerate() {
+            inv[from] = to;
+        }
+        inv
+    } else {
+        perm
+    };
+    if let Some(path) = permutation {
+        let mut w = create_file(&path, output);
+        for i in &order {
+            writeln!(w, "{i}").unwrap();
+        }
+        finish_file(w);
+    }
+    if let Some(path) = dest {
+        let mut w = create_file(&path, output);
+        write_lines(&mut w, &lines, &order);
+        finish_file(w);
+    } else {
+        write_lines(stdout().lock(), &lines, &order);
+    }
+}
+
+fn write_lines(mut w: impl Write, lines: &[String], order: &[usize]) {
+    for &i in order {
+        writeln!(w, "{}", lines[i]).unwrap();
+    }
+    w.flush().unwrap();
+}
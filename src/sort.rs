@@ -0,0 +1,67 @@
+//! Orders for lists of sources, so numbered shares come out the same way every time
+
+use clap::ArgEnum;
+
+use std::{cmp::Ordering, path::PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum SortOrder {
+    /// Runs of digits by their value, so `share_2` comes before `share_10`
+    Natural,
+    /// Byte by byte, so `share_10` comes before `share_2`
+    Lexical,
+}
+
+/// Sort `paths` in `order`, keeping equal ones as they were
+pub fn sort(paths: &mut [PathBuf], order: SortOrder) {
+    match order {
+        SortOrder::Natural => paths.sort_by(|a, b| {
+            natural_cmp(
+                a.as_os_str().as_encoded_bytes(),
+                b.as_os_str().as_encoded_bytes(),
+            )
+        }),
+        SortOrder::Lexical => paths.sort(),
+    }
+}
+
+/// Compare runs of digits by value, leading zeros breaking ties, and everything else bytewise
+fn natural_cmp(mut a: &[u8], mut b: &[u8]) -> Ordering {
+    loop {
+        match (a.first(), b.first()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, rest_a) = a.split_at(digits(a));
+                let (y, rest_b) = b.split_at(digits(b));
+                let by_value = trim(x)
+                    .len()
+                    .cmp(&trim(y).len())
+                    .then_with(|| trim(x).cmp(trim(y)))
+                    .then_with(|| x.len().cmp(&y.len()));
+                if by_value != Ordering::Equal {
+                    return by_value;
+                }
+                (a, b) = (rest_a, rest_b);
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(y);
+                }
+                (a, b) = (&a[1..], &b[1..]);
+            }
+        }
+    }
+}
+
+/// Length of the run of ASCII digits `s` starts with
+fn digits(s: &[u8]) -> usize {
+    s.iter().take_while(|b| b.is_ascii_digit()).count()
+}
+
+/// The digits `n` without leading zeros
+fn trim(n: &[u8]) -> &[u8] {
+    let zeros = n.iter().take_while(|&&d| d == b'0').count();
+    &n[zeros..]
+}
@@ -0,0 +1,186 @@
+//! Reading the shares of `xor --from-tar` out of a tar archive, without unpacking it
+//!
+//! Only regular files are taken, as ustar, GNU and pax archives have them.
+//! `.meta` sidecars that travel along describe their files instead of becoming sources.
+//! Entries come in the order of their names, so the same archive always numbers them
+//! the same way.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, Read},
+    path::Path,
+};
+
+use smallvec::SmallVec;
+
+use crate::{
+    interleave::Lines,
+    log,
+    meta::{self, Sidecar},
+    open_input, Input,
+};
+
+const BLOCK: usize = 512;
+
+/// The files of an archive, in the order of their names
+pub struct Entries {
+    pub inputs: SmallVec<[Input; 4]>,
+    /// The size of each file
+    pub sizes: Vec<u64>,
+    /// The `.meta` of the files that came with one, as if the archive were a directory
+    pub sidecars: Vec<Sidecar>,
+}
+
+/// The regular files in the archive at `path`
+pub fn entries(path: &Path) -> Entries {
+    let mut archive = open_input(path);
+    let fail = |what: &str| -> ! { panic!("{} isn't a tar archive: {what}", path.display()) };
+    let (mut files, mut metas) = (Vec::new(), HashMap::new());
+    let (mut long_name, mut pax_name, mut pax_size) = (None, None, None);
+    loop {
+        let mut header = [0; BLOCK];
+        if archive.read_exact(&mut header).is_err() {
+            fail("it ends without the closing zero blocks");
+        }
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if Some(checksum(&header)) != octal(&header[148..156]) {
+            fail("a header doesn't match its checksum");
+        }
+        let size = pax_size
+            .take()
+            .unwrap_or_else(|| octal(&header[124..136]).unwrap_or_else(|| fail("bad size")));
+        let size = usize::try_from(size).unwrap_or_else(|_| fail("an entry is too big"));
+        let data = read_data(&mut archive, size).unwrap_or_else(|| fail("it ends in an entry"));
+        match header[156] {
+            b'L' => long_name = Some(until_nul(&data)),
+            b'x' => {
+                for (key, value) in pax_records(&data) {
+                    match key.as_str() {
+                        "path" => pax_name = Some(value),
+                        "size" => pax_size = value.parse().ok(),
+                        _ => {}
+                    }
+                }
+            }
+            b'0' | 0 => {
+                let name = pax_name
+                    .take()
+                    .or_else(|| long_name.take())
+                    .unwrap_or_else(|| {
+                        let prefix = until_nul(&header[345..500]);
+                        let name = until_nul(&header[..100]);
+                        if prefix.is_empty() {
+                            name
+                        } else {
+                            format!("{prefix}/{name}")
+                        }
+                    });
+                match name.strip_suffix(".meta") {
+                    Some(file) => _ = metas.insert(file.to_owned(), data),
+                    None => files.push((name, data)),
+                }
+            }
+            _ => (long_name, pax_name) = (None, None),
+        }
+    }
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut sidecars = Vec::new();
+    for (f, (name, _)) in files.iter().enumerate() {
+        log::info(format_args!(
+            "file {} is {name} from {}",
+            f + 1,
+            path.display()
+        ));
+        let Some(data) = metas.remove(name) else {
+            continue;
+        };
+        let member = path.join(name);
+        let text = String::from_utf8(data)
+            .unwrap_or_else(|_| panic!("{name}.meta in {} isn't UTF-8 text", path.display()));
+        sidecars.push(Sidecar {
+            file: f,
+            meta: meta::parse(&text, &meta::path(&member)),
+            path: member,
+        });
+    }
+    for name in metas.keys() {
+        log::info(format_args!(
+            "leaving out {name}.meta from {}, there's no {name} in it",
+            path.display()
+        ));
+    }
+    Entries {
+        sizes: files.iter().map(|(_, data)| data.len() as u64).collect(),
+        inputs: files
+            .into_iter()
+            .map(|(_, data)| Box::new(Lines::new(data)) as Input)
+            .collect(),
+        sidecars,
+    }
+}
+
+/// The `size` bytes of an entry, skipping the padding to the next block
+fn read_data(archive: &mut impl BufRead, size: usize) -> Option<Vec<u8>> {
+    let mut data = vec![0; size];
+    let padding = (BLOCK - size % BLOCK) % BLOCK;
+    let read = archive
+        .read_exact(&mut data)
+        .and_then(|()| archive.read_exact(&mut [0; BLOCK][..padding]));
+    if read.is_err() {
+        data.fill(0);
+        return None;
+    }
+    Some(data)
+}
+
+/// The sum of all bytes of `header`, with its own checksum field counted as spaces
+fn checksum(header: &[u8; BLOCK]) -> u64 {
+    let spaces = 8 * u64::from(b' ');
+    header[..148]
+        .iter()
+        .chain(&header[156..])
+        .map(|&b| u64::from(b))
+        .sum::<u64>()
+        + spaces
+}
+
+/// A number field, in octal or, when its first byte has the high bit set, GNU base-256
+fn octal(field: &[u8]) -> Option<u64> {
+    if field.first().is_some_and(|&b| b & 0x80 != 0) {
+        return field[1..]
+            .iter()
+            .try_fold(u64::from(field[0] & 0x7f), |n, &b| {
+                n.checked_mul(256)?.checked_add(u64::from(b))
+            });
+    }
+    let text = std::str::from_utf8(field).ok()?;
+    u64::from_str_radix(text.trim_matches(|c| c == ' ' || c == '\0'), 8).ok()
+}
+
+/// A name field up to its first NUL
+fn until_nul(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// The `key=value` pairs of a pax header, each a line starting with its own length
+fn pax_records(mut data: &[u8]) -> Vec<(String, String)> {
+    let mut records = Vec::new();
+    while let Some(space) = data.iter().position(|&b| b == b' ') {
+        let Some(len) = std::str::from_utf8(&data[..space])
+            .ok()
+            .and_then(|len| len.parse::<usize>().ok())
+            .filter(|&len| len > space && len <= data.len())
+        else {
+            break;
+        };
+        let record = String::from_utf8_lossy(&data[space + 1..len]);
+        if let Some((key, value)) = record.trim_end_matches('\n').split_once('=') {
+            records.push((key.to_owned(), value.to_owned()));
+        }
+        data = &data[len..];
+    }
+    records
+}
@@ -0,0 +1,104 @@
+//! Re-encoding files line by line, with a hook on each line's entropy in between
+//!
+//! [`transform`] is the building block, `convert` is it with the hook doing nothing.
+
+use clap::Args;
+
+use std::{
+    io::{stdout, BufRead, Write},
+    path::PathBuf,
+};
+
+use crate::{
+    check_width, create_file,
+    error::Failure,
+    finish_file,
+    format::{Encoding, Format, Style},
+    meta, open_input, read_data_line, report, skip_lines, Output, Range,
+};
+
+#[derive(Debug, Args)]
+pub struct Convert {
+    #[clap(parse(from_os_str))]
+    source: PathBuf,
+    /// File to write the result to, stdout when left out or `-`
+    #[clap(short = 'o', long = "out", parse(from_os_str))]
+    dest: Option<PathBuf>,
+    /// Encoding to write the lines in, the other options apply to both sides
+    #[clap(long, arg_enum, value_name = "FORMAT")]
+    to_format: Format,
+    #[clap(flatten)]
+    range: Range,
+    #[clap(flatten)]
+    style: Style,
+    #[clap(flatten)]
+    output: Output,
+}
+
+pub fn run(
+    Convert {
+        source,
+        dest,
+        to_format,
+        mut range,
+        style,
+        output,
+    }: Convert,
+) {
+    let from = meta::configure(std::slice::from_ref(&source), style, &mut range);
+    report::style(&from);
+    let to = Style {
+        format: to_format,
+        ..from.clone()
+    };
+    let mut file = open_input(&source);
+    match dest.filter(|path| path.as_os_str() != "-") {
+        Some(path) => {
+            let mut w = create_file(&path, output);
+            transform(&mut file, &mut w, &from, &to, from.bytes(), range, |_| {});
+            finish_file(w);
+        }
+        None => {
+            transform(
+                &mut file,
+                stdout().lock(),
+                &from,
+                &to,
+                from.bytes(),
+                range,
+                |_| {},
+            );
+        }
+    }
+}
+
+/// Decode each line of `range` of `reader` from `from`, let `f` change its `width` bytes of entropy,
+/// and write it to `writer` in `to`, returning how many lines that was
+pub fn transform<R: BufRead, W: Write, F: FnMut(&mut [u8])>(
+    reader: &mut R,
+    mut writer: W,
+    from: &impl Encoding,
+    to: &impl Encoding,
+    width: usize,
+    range: Range,
+    mut f: F,
+) -> usize {
+    let str = &mut String::new();
+    skip_lines(reader, range.read_past());
+    let mut i = range.skip();
+    while !range.done(i) && read_data_line(reader, str, None, i) != 0 {
+        let mut entropy = match from.decode(str, i + 1) {
+            Ok(entropy) => entropy,
+            Err(e) => Failure::new("parse", e).line(i).raise(),
+        };
+        str.clear();
+        check_width(&entropy, width, None, i);
+        f(&mut entropy);
+        to.write(&mut writer, i - range.skip() + 1, &entropy)
+            .unwrap_or_else(|err| panic!("couldn't write: {err}"));
+        i += 1;
+    }
+    report::lines(i - range.skip());
+    range.finish(i);
+    i - range.skip()
+}
@@ -0,0 +1,83 @@
+//! `--verify-written`, reading outputs back once they're written, before anything relies on them
+//!
+//! That catches what the writes themselves didn't complain about, like a filesystem losing data.
+//! Standard input can't be read twice, so a secret from it is never compared again.
+
+use std::path::Path;
+
+use crate::{
+    check_width,
+    error::Failure,
+    format::{Encoding, Style},
+    is_null_device, log, open_input, read_data_line, skip_lines, Range,
+};
+
+/// Check that `path`, file `f` of an operation, reads back as `lines` lines of the width of `style`
+pub fn lines(path: &Path, f: usize, style: &Style, lines: usize) {
+    if is_null_device(path) {
+        return;
+    }
+    let mut file = open_input(path);
+    let str = &mut String::new();
+    let mut i = 0;
+    while read_data_line(&mut file, str, Some(f), i) != 0 {
+        match style.decode(str, i + 1) {
+            Ok(entropy) => check_width(&entropy, style.bytes(), Some(f), i),
+            Err(e) => Failure::new(
+                "verify",
+                format_args!("{} doesn't read back: {e}", path.display()),
+            )
+            .file(f)
+            .line(i)
+            .raise(),
+        }
+        str.clear();
+        i += 1;
+    }
+    if i != lines {
+        Failure::new(
+            "verify",
+            format_args!(
+                "{} reads back as {i} lines instead of {lines}",
+                path.display()
+            ),
+        )
+        .file(f)
+        .raise();
+    }
+    log::info(format_args!("read back {}", path.display()));
+}
+
+/// Check that `combined`, what the shares XOR to, is `range` of the secret at `source`
+pub fn matches_source(source: &Path, mut combined: &[u8], style: &Style, range: Range) {
+    if source.as_os_str() == "-" {
+        log::warn(format_args!(
+            "the secret came from standard input, so the shares weren't compared to it"
+        ));
+        return;
+    }
+    let mut original = open_input(source);
+    skip_lines(&mut original, range.read_past());
+    let (line, back) = (&mut String::new(), &mut String::new());
+    let mut i = range.skip();
+    while !range.done(i) && read_data_line(&mut original, line, None, i) != 0 {
+        let n = i - range.skip();
+        let same = read_data_line(&mut combined, back, None, n) != 0
+            && matches!(
+                (style.decode(line, i + 1), style.decode(back, n + 1)),
+                (Ok(a), Ok(b)) if crate::ct::eq(&a, &b)
+            );
+        if !same {
+            Failure::new("verify", "the shares don't XOR back to the secret")
+                .line(i)
+                .raise();
+        }
+        line.clear();
+        back.clear();
+        i += 1;
+    }
+    if read_data_line(&mut combined, back, None, i - range.skip()) != 0 {
+        Failure::new("verify", "the shares XOR to more lines than the secret has").raise();
+    }
+    eprintln!("the shares XOR back to the secret");
+}
@@ -0,0 +1,82 @@
+//! `verify-wordlist`, checking the compiled in wordlists against digests of the BIP39 ones
+//!
+//! A digest is the SHA-256 of a list written the way the BIP39 repository has it as a file,
+//! each word followed by a newline, so `sha256sum english.txt` gives the same.
+
+use bip39::Language;
+use bitcoin_hashes::{hex::ToHex, sha256, Hash, HashEngine};
+use clap::Args;
+
+use crate::{error::Failure, format};
+
+#[derive(Debug, Args)]
+pub struct VerifyWordlist {
+    /// Only check this wordlist, instead of all of them in this build
+    #[clap(long, parse(try_from_str = format::parse_language))]
+    language: Option<Language>,
+}
+
+pub fn run(VerifyWordlist { language }: VerifyWordlist) {
+    let languages = language.map_or_else(|| Language::all().to_vec(), |language| vec![language]);
+    let mismatched: Vec<_> = languages
+        .into_iter()
+        .filter(|&language| {
+            let ok = matches(language);
+            println!(
+                "{} {}",
+                if ok { "PASS" } else { "FAIL" },
+                format::language_name(language)
+            );
+            !ok
+        })
+        .collect();
+    if !mismatched.is_empty() {
+        let names: Vec<_> = mismatched.into_iter().map(format::language_name).collect();
+        Failure::new(
+            "wordlist",
+            format_args!(
+                "the {} wordlist isn't the BIP39 one, don't trust mnemonics from this build",
+                names.join(", ")
+            ),
+        )
+        .raise();
+    }
+}
+
+/// Whether the wordlist of `language` is exactly the BIP39 one
+pub fn matches(language: Language) -> bool {
+    let words = language.words_by_prefix("");
+    let mut engine = sha256::Hash::engine();
+    for word in words {
+        engine.input(word.as_bytes());
+        engine.input(b"\n");
+    }
+    words.len() == 2048 && sha256::Hash::from_engine(engine).to_hex() == expected(language)
+}
+
+/// The SHA-256 of the BIP39 file of `language`
+const fn expected(language: Language) -> &'static str {
+    match language {
+        Language::English => "2f5eed53a4727b4bf8880d8f3f199efc90e58503646d9ff8eff3a2ed3b24dbda",
+        #[cfg(feature = "chinese-simplified")]
+        Language::SimplifiedChinese => {
+            "5c5942792bd8340cb8b27cd592f1015edf56a8c5b26276ee18a482428e7c5726"
+        }
+        #[cfg(feature = "chinese-traditional")]
+        Language::TraditionalChinese => {
+            "417b26b3d8500a4ae3d59717d7011952db6fc2fb84b807f3f94ac734e89c1b5f"
+        }
+        #[cfg(feature = "czech")]
+        Language::Czech => "7e80e161c3e93d9554c2efb78d4e3cebf8fc727e9c52e03b83b94406bdcc95fc",
+        #[cfg(feature = "french")]
+        Language::French => "ebc3959ab7801a1df6bac4fa7d970652f1df76b683cd2f4003c941c63d517e59",
+        #[cfg(feature = "italian")]
+        Language::Italian => "d392c49fdb700a24cd1fceb237c1f65dcc128f6b34a8aacb58b59384b5c648c2",
+        #[cfg(feature = "japanese")]
+        Language::Japanese => "2eed0aef492291e061633d7ad8117f1a2b03eb80a29d0e4e3117ac2528d05ffd",
+        #[cfg(feature = "korean")]
+        Language::Korean => "9e95f86c167de88f450f0aaf89e87f6624a57f973c67b516e338e8e8b8897f60",
+        #[cfg(feature = "spanish")]
+        Language::Spanish => "46846a5a0139d1e3cb77293e521c2865f7bcdb82c44e8d0a06a2cd0ecba48c0b",
+    }
+}